@@ -1,21 +1,20 @@
 #!/bin/bash
 
-if [ $1 -lt 1 -o $1 -gt 25 ]; then
+if [ "x$1" == "x" ]; then
 	echo "Must give day number"
 	exit 1
 fi
 
-day=$1
-daypad="$(printf %02d $day)"
-
-token="$(cat token.txt)"
-if [ "x$token" == "x" ]; then
-	echo "No token"
-	exit 2
+if [ $1 -lt 1 -o $1 -gt 25 ]; then
+	echo "Must give day number"
+	exit 1
 fi
 
-curl -sS --cookie "session=$token" https://adventofcode.com/2017/day/$day/input -o inputs/day$daypad.txt
+# Downloads to inputs/ unless the input already exists.
+# Session token comes from AOC_TOKEN or token.txt, server from AOC_BASE_URL
+cargo run --quiet --bin getinput -- $1
 
 if [ $? -ne 0 ]; then
 	echo "Failed to download"
+	exit 2
 fi
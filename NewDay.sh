@@ -25,13 +25,13 @@ find "$dir" -type f -print | while read file
 do
     case $(uname) in
     Darwin)
-        sed -i '' "s/\$day/$day/g" "$file"
         sed -i '' "s/\$daypad/$daypad/g" "$file"
+        sed -i '' "s/\$day/$day/g" "$file"
         sed -i '' "s/\$dir/$dir/g" "$file"
         ;;
     *)
-        sed -i "s/\$day/$day/g" "$file"
         sed -i "s/\$daypad/$daypad/g" "$file"
+        sed -i "s/\$day/$day/g" "$file"
         sed -i "s/\$dir/$dir/g" "$file"
         ;;
     esac
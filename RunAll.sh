@@ -0,0 +1,15 @@
+#!/bin/bash
+
+# Runs days (all by default) through the runner.
+# Pass --profiles to run each day against every input profile in inputs/<profile>/
+# and --jobs n to limit the number of parts run in parallel
+
+cargo build --release --workspace --quiet
+
+if [ $? -ne 0 ]
+then
+	echo "Build failed"
+	exit 2
+fi
+
+target/release/runner "$@"
@@ -0,0 +1,31 @@
+use std::{env, error::Error, process::exit};
+
+use aoc::fetch::{Fetched, Fetcher};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Get day numbers from the command line
+    let days = env::args()
+        .skip(1)
+        .map(|a| a.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if days.is_empty() {
+        eprintln!("Must give day number(s)");
+        exit(1);
+    }
+
+    let fetcher = Fetcher::from_env();
+
+    for day in days {
+        match fetcher.fetch(day) {
+            Ok(Fetched::Cached(path)) => println!("Day {day}: {} already exists", path.display()),
+            Ok(Fetched::Downloaded(path)) => println!("Day {day}: downloaded {}", path.display()),
+            Err(e) => {
+                eprintln!("Day {day}: {e}");
+                exit(2);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use std::{env, error::Error, path::PathBuf, process::exit, thread};
+
+use aoc::fetch::StandIn;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    if args.len() != 2 {
+        eprintln!("Usage: standin <fixture dir> <session token>");
+        exit(1);
+    }
+
+    let server = StandIn::start(&PathBuf::from(&args[0]), &args[1])?;
+
+    println!("AOC_BASE_URL={}", server.base_url());
+
+    // Serve until killed
+    loop {
+        thread::park();
+    }
+}
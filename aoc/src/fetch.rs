@@ -0,0 +1,427 @@
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fmt, fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::input;
+
+/// Puzzle year
+pub const YEAR: u16 = 2017;
+
+/// Default puzzle server
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+
+/// Downloads puzzle inputs in to the input cache directory
+pub struct Fetcher {
+    base_url: String,
+    token: Option<String>,
+    cache_dir: PathBuf,
+}
+
+impl Fetcher {
+    /// Creates a new fetcher with a given server base URL, session token and cache directory
+    pub fn new(base_url: &str, token: Option<String>, cache_dir: &Path) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            cache_dir: cache_dir.to_path_buf(),
+        }
+    }
+
+    /// Creates a fetcher configured from the environment.
+    /// The base URL is taken from AOC_BASE_URL and the session token from AOC_TOKEN or token.txt.
+    /// Inputs are cached in the directory of the selected input profile
+    pub fn from_env() -> Self {
+        let base_url = env::var("AOC_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+
+        let token = match env::var("AOC_TOKEN") {
+            Ok(token) => Some(token),
+            Err(_) => fs::read_to_string(input::inputs_dir().with_file_name("token.txt")).ok(),
+        }
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty());
+
+        let mut cache_dir = input::inputs_dir();
+
+        if let Some(profile) = input::profile() {
+            cache_dir.push(profile);
+        }
+
+        Self::new(&base_url, token, &cache_dir)
+    }
+
+    /// Returns the cache path for a given day
+    pub fn cache_path(&self, day: usize) -> PathBuf {
+        self.cache_dir.join(format!("day{day:02}.txt"))
+    }
+
+    /// Returns the URL for a given day's input
+    pub fn url(&self, day: usize) -> String {
+        format!("{}/{YEAR}/day/{day}/input", self.base_url)
+    }
+
+    /// Makes sure the input for a given day is in the cache, downloading it if necessary
+    pub fn fetch(&self, day: usize) -> Result<Fetched, FetchError> {
+        if !(1..=25).contains(&day) {
+            return Err(FetchError::InvalidDay(day));
+        }
+
+        let path = self.cache_path(day);
+
+        // Never download an input twice
+        if path.exists() {
+            return Ok(Fetched::Cached(path));
+        }
+
+        let token = self.token.as_ref().ok_or(FetchError::NoToken)?;
+
+        // Download the input
+        let url = self.url(day);
+
+        let response = ureq::get(&url)
+            .set("Cookie", &format!("session={token}"))
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(status, response) => FetchError::Http {
+                    url: url.clone(),
+                    status,
+                    message: response
+                        .into_string()
+                        .unwrap_or_default()
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                },
+                ureq::Error::Transport(transport) => FetchError::Transport {
+                    url: url.clone(),
+                    message: transport.to_string(),
+                },
+            })?;
+
+        let body = response.into_string().map_err(|e| FetchError::Transport {
+            url: url.clone(),
+            message: e.to_string(),
+        })?;
+
+        if body.is_empty() {
+            return Err(FetchError::Empty(url));
+        }
+
+        // Write to a temporary file and move in to place so a partial download is never cached
+        fs::create_dir_all(&self.cache_dir)?;
+
+        let tmp_path = path.with_extension("part");
+        fs::write(&tmp_path, body)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(Fetched::Downloaded(path))
+    }
+}
+
+/// Result of a successful fetch
+#[derive(Debug, PartialEq, Eq)]
+pub enum Fetched {
+    Cached(PathBuf),
+    Downloaded(PathBuf),
+}
+
+/// Fetch failure reasons
+#[derive(Debug)]
+pub enum FetchError {
+    InvalidDay(usize),
+    NoToken,
+    Http {
+        url: String,
+        status: u16,
+        message: String,
+    },
+    Transport {
+        url: String,
+        message: String,
+    },
+    Empty(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::InvalidDay(day) => write!(f, "Day {day} is not between 1 and 25"),
+            FetchError::NoToken => write!(
+                f,
+                "No session token - set AOC_TOKEN or put the session cookie in token.txt"
+            ),
+            FetchError::Http {
+                url,
+                status,
+                message,
+            } => {
+                write!(f, "Server returned HTTP {status} for {url}")?;
+
+                if !message.is_empty() {
+                    write!(f, ": {message}")?;
+                }
+
+                Ok(())
+            }
+            FetchError::Transport { url, message } => {
+                write!(f, "Failed to download {url}: {message}")
+            }
+            FetchError::Empty(url) => write!(f, "Server returned an empty input for {url}"),
+            FetchError::Io(e) => write!(f, "Failed to write input cache: {e}"),
+        }
+    }
+}
+
+impl Error for FetchError {}
+
+impl From<std::io::Error> for FetchError {
+    fn from(e: std::io::Error) -> Self {
+        FetchError::Io(e)
+    }
+}
+
+/// Time the stand-in waits for a client to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Local stand-in for the puzzle server.
+/// Serves dayNN.txt files from a fixture directory to requests carrying the expected session cookie
+pub struct StandIn {
+    addr: String,
+    requests: Arc<AtomicUsize>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl StandIn {
+    /// Starts a stand-in server on a free local port
+    pub fn start(fixture_dir: &Path, token: &str) -> Result<Self, Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?.to_string();
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let fixture_dir = fixture_dir.to_path_buf();
+        let cookie = format!("session={token}");
+
+        let handle = {
+            let requests = requests.clone();
+            let stop = stop.clone();
+
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    if let Ok(stream) = stream {
+                        requests.fetch_add(1, Ordering::SeqCst);
+
+                        // Errors just drop the connection
+                        let _ = Self::serve(stream, &fixture_dir, &cookie);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            requests,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the base URL of the server
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns the number of requests served
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    fn serve(mut stream: TcpStream, fixture_dir: &Path, cookie: &str) -> std::io::Result<()> {
+        // Don't let a client which never sends anything block the server
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        // Read the request line
+        let mut request = String::new();
+        reader.read_line(&mut request)?;
+
+        // Read the headers
+        let mut headers = HashMap::new();
+
+        loop {
+            let mut line = String::new();
+
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+        let (status, body) = match headers.get("cookie") {
+            Some(c) if c == cookie => {
+                let file = path
+                    .strip_prefix(&format!("/{YEAR}/day/"))
+                    .and_then(|rest| rest.strip_suffix("/input"))
+                    .and_then(|day| day.parse::<usize>().ok())
+                    .and_then(|day| {
+                        fs::read_to_string(fixture_dir.join(format!("day{day:02}.txt"))).ok()
+                    });
+
+                match file {
+                    Some(body) => ("200 OK", body),
+                    None => ("404 Not Found", "Not found\n".to_string()),
+                }
+            }
+            _ => (
+                "400 Bad Request",
+                "Puzzle inputs differ by user.  Please log in to get your puzzle input.\n"
+                    .to_string(),
+            ),
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+
+        stream.flush()
+    }
+}
+
+impl Drop for StandIn {
+    fn drop(&mut self) {
+        // Wake the listener up so it sees the stop flag
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(&self.addr);
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("aoc{YEAR}-{name}-{}", std::process::id()));
+
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn setup(name: &str) -> (PathBuf, PathBuf, StandIn) {
+        let dir = scratch_dir(name);
+
+        let fixtures = dir.join("fixtures");
+        fs::create_dir_all(&fixtures).unwrap();
+        fs::write(fixtures.join("day01.txt"), "1122\n").unwrap();
+
+        let cache = dir.join("inputs");
+
+        let server = StandIn::start(&fixtures, TOKEN).unwrap();
+
+        (dir, cache, server)
+    }
+
+    #[test]
+    fn download_and_cache() {
+        let (dir, cache, server) = setup("download");
+
+        let fetcher = Fetcher::new(&server.base_url(), Some(TOKEN.to_string()), &cache);
+
+        let path = cache.join("day01.txt");
+
+        assert_eq!(fetcher.fetch(1).unwrap(), Fetched::Downloaded(path.clone()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1122\n");
+        assert_eq!(server.requests(), 1);
+
+        // Second fetch must come from the cache
+        assert_eq!(fetcher.fetch(1).unwrap(), Fetched::Cached(path));
+        assert_eq!(server.requests(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn stuck_client() {
+        let (dir, cache, server) = setup("stuck");
+
+        // Connect without sending a request
+        let _stuck = TcpStream::connect(server.base_url().trim_start_matches("http://")).unwrap();
+
+        let fetcher = Fetcher::new(&server.base_url(), Some(TOKEN.to_string()), &cache);
+
+        assert!(matches!(fetcher.fetch(1), Ok(Fetched::Downloaded(_))));
+        assert_eq!(server.requests(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let (dir, cache, server) = setup("errors");
+
+        // No token
+        let fetcher = Fetcher::new(&server.base_url(), None, &cache);
+        assert!(matches!(fetcher.fetch(1), Err(FetchError::NoToken)));
+        assert_eq!(server.requests(), 0);
+
+        // Invalid day
+        assert!(matches!(fetcher.fetch(26), Err(FetchError::InvalidDay(26))));
+
+        // Bad token
+        let fetcher = Fetcher::new(&server.base_url(), Some("bad".to_string()), &cache);
+        assert!(matches!(
+            fetcher.fetch(1),
+            Err(FetchError::Http { status: 400, .. })
+        ));
+
+        // Missing fixture
+        let fetcher = Fetcher::new(&server.base_url(), Some(TOKEN.to_string()), &cache);
+        assert!(matches!(
+            fetcher.fetch(2),
+            Err(FetchError::Http { status: 404, .. })
+        ));
+
+        // Nothing should have been cached
+        assert!(!cache.join("day01.txt").exists());
+        assert!(!cache.join("day02.txt").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// Undirected graph with nodes identified by keys. Nodes are numbered in the order
+/// they are added
+#[derive(Debug, Clone)]
+pub struct Graph<K> {
+    keys: Vec<K>,
+    index: HashMap<K, usize>,
+    adjacent: Vec<Vec<usize>>,
+    /// Edges added so far as (lower node, higher node)
+    edges: HashSet<(usize, usize)>,
+}
+
+impl<K> Default for Graph<K> {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            index: HashMap::new(),
+            adjacent: Vec::new(),
+            edges: HashSet::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> Graph<K> {
+    /// Creates an empty graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of a node, adding it if it isn't in the graph
+    pub fn add_node(&mut self, key: K) -> usize {
+        if let Some(&node) = self.index.get(&key) {
+            return node;
+        }
+
+        let node = self.keys.len();
+
+        self.keys.push(key.clone());
+        self.index.insert(key, node);
+        self.adjacent.push(Vec::new());
+
+        node
+    }
+
+    /// Adds an edge between two nodes, adding the nodes if needed
+    pub fn add_edge(&mut self, a: K, b: K) {
+        let a = self.add_node(a);
+        let b = self.add_node(b);
+
+        if self.edges.insert((a.min(b), a.max(b))) {
+            self.adjacent[a].push(b);
+
+            if a != b {
+                self.adjacent[b].push(a);
+            }
+        }
+    }
+
+    /// Returns the number of nodes
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns true if there are no nodes
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the number of the node with a key
+    pub fn node(&self, key: &K) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
+    /// Returns the key of a node
+    pub fn key(&self, node: usize) -> &K {
+        &self.keys[node]
+    }
+
+    /// Returns the nodes joined to a node
+    pub fn neighbours(&self, node: usize) -> &[usize] {
+        &self.adjacent[node]
+    }
+
+    /// Returns the nodes reachable from a node in breadth first order
+    pub fn bfs(&self, start: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.len()];
+
+        self.bfs_from(start, &mut seen)
+    }
+
+    fn bfs_from(&self, start: usize, seen: &mut [bool]) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([start]);
+
+        seen[start] = true;
+
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+
+            for &next in &self.adjacent[node] {
+                if !seen[next] {
+                    seen[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Splits the graph into connected components
+    pub fn components(&self) -> Components<'_, K> {
+        let mut seen = vec![false; self.len()];
+        let mut component = vec![0; self.len()];
+        let mut members = Vec::new();
+
+        for start in 0..self.len() {
+            if seen[start] {
+                continue;
+            }
+
+            let mut nodes = self.bfs_from(start, &mut seen);
+
+            nodes.sort_unstable();
+
+            for &node in &nodes {
+                component[node] = members.len();
+            }
+
+            members.push(nodes);
+        }
+
+        Components {
+            graph: self,
+            component,
+            members,
+        }
+    }
+}
+
+/// Connected components of a graph, numbered in order of their first node
+pub struct Components<'a, K> {
+    graph: &'a Graph<K>,
+    /// Component number of each node
+    component: Vec<usize>,
+    /// Nodes in each component in node order
+    members: Vec<Vec<usize>>,
+}
+
+impl<K: Hash + Eq + Clone> Components<'_, K> {
+    /// Returns the number of components
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns true if there are no components
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns the component number containing a key
+    pub fn component(&self, key: &K) -> Option<usize> {
+        self.graph.node(key).map(|node| self.component[node])
+    }
+
+    /// Returns the nodes in a component
+    pub fn nodes(&self, component: usize) -> &[usize] {
+        &self.members[component]
+    }
+
+    /// Returns the keys in a component
+    pub fn members(&self, component: usize) -> Vec<&K> {
+        self.members[component]
+            .iter()
+            .map(|&node| self.graph.key(node))
+            .collect()
+    }
+
+    /// Returns the number of nodes in a component
+    pub fn size(&self, component: usize) -> usize {
+        self.members[component].len()
+    }
+
+    /// Returns the number of nodes in each component
+    pub fn sizes(&self) -> Vec<usize> {
+        self.members.iter().map(|m| m.len()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_components() {
+        let mut graph = Graph::new();
+
+        graph.add_edge("a", "b");
+        graph.add_edge("c", "d");
+        graph.add_edge("b", "e");
+        graph.add_edge("e", "a");
+        graph.add_edge("f", "f");
+        graph.add_node("g");
+
+        assert_eq!(graph.len(), 7);
+        assert_eq!(graph.neighbours(0), [1, 4]);
+        assert_eq!(graph.neighbours(5), [5]);
+        assert_eq!(graph.bfs(1), [1, 0, 4]);
+
+        let components = graph.components();
+
+        assert_eq!(components.len(), 4);
+        assert_eq!(components.sizes(), [3, 2, 1, 1]);
+        assert_eq!(components.members(0), [&"a", &"b", &"e"]);
+        assert_eq!(components.nodes(1), [2, 3]);
+        assert_eq!(components.component(&"d"), Some(1));
+        assert_eq!(components.component(&"g"), Some(3));
+        assert_eq!(components.size(3), 1);
+        assert_eq!(components.component(&"x"), None);
+    }
+
+    #[test]
+    fn test_long_chain() {
+        // Deep enough to overflow the stack if walked recursively
+        let mut graph = Graph::new();
+
+        for i in 0..200_000u32 {
+            graph.add_edge(i, i + 1);
+        }
+
+        let components = graph.components();
+
+        assert_eq!(components.sizes(), [200_001]);
+        assert!(Graph::<u32>::new().components().is_empty());
+    }
+
+    #[test]
+    fn test_star() {
+        // Edges to a high degree node are added in constant time, and repeats in
+        // either direction are ignored
+        let mut graph = Graph::new();
+
+        for i in 1..=100_000u32 {
+            graph.add_edge(0, i);
+            graph.add_edge(i, 0);
+        }
+
+        assert_eq!(graph.neighbours(0).len(), 100_000);
+        assert_eq!(graph.neighbours(1), [0]);
+        assert_eq!(graph.components().sizes(), [100_001]);
+    }
+}
@@ -1,8 +1,10 @@
+use std::env;
 use std::error::Error;
-use std::fs::File;
+use std::fs::{self, File};
 #[cfg(miri)]
 use std::io::Read;
 use std::io::{BufRead, BufReader, Lines};
+use std::path::PathBuf;
 
 #[cfg(not(miri))]
 use memmap2::Mmap;
@@ -47,6 +49,60 @@ where
     parse_buf_vec(input.lines(), tfn)
 }
 
+/// Environment variable selecting the input profile
+pub const PROFILE_VAR: &str = "AOC_PROFILE";
+
+/// Returns the selected input profile, if any.
+/// Profile inputs are read from inputs/<profile>/dayNN.txt instead of inputs/dayNN.txt
+pub fn profile() -> Option<String> {
+    env::var(PROFILE_VAR).ok().filter(|p| !p.is_empty())
+}
+
+/// Environment variable selecting a single part to run
+pub const PART_VAR: &str = "AOC_PART";
+
+/// Returns true if a part should be run. Every part is run unless one is selected
+pub fn run_part(part: usize) -> bool {
+    match env::var(PART_VAR) {
+        Ok(selected) if !selected.is_empty() => selected == part.to_string(),
+        _ => true,
+    }
+}
+
+/// Returns the names of all input profiles (subdirectories of either inputs directory)
+pub fn profiles() -> Result<Vec<String>, Box<dyn Error>> {
+    let mut profiles = Vec::new();
+
+    for dir in ["inputs", "../inputs"] {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries {
+            let entry = entry?;
+
+            if entry.file_type()?.is_dir() {
+                profiles.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    profiles.sort();
+    profiles.dedup();
+
+    Ok(profiles)
+}
+
+/// Returns the path of the inputs directory to write to, checking the current directory
+/// and then the parent. Inputs are read from whichever one has the file
+pub fn inputs_dir() -> PathBuf {
+    match fs::metadata("inputs") {
+        Ok(m) if m.is_dir() => PathBuf::from("inputs"),
+        _ => PathBuf::from("../inputs"),
+    }
+}
+
 /// Memory mapped input
 struct Input {
     #[cfg(not(miri))]
@@ -57,8 +113,12 @@ struct Input {
 
 impl Input {
     fn new(day: usize) -> Result<Self, Box<dyn Error>> {
-        // Open the file
-        let file = Self::open(&format!("day{day:02}.txt"))?;
+        // Open the file, from the profile directory if one is selected
+        let file = match profile() {
+            Some(profile) => Self::open(&format!("{profile}/day{day:02}.txt"))
+                .map_err(|e| format!("Input for day {day} in profile {profile}: {e}"))?,
+            None => Self::open(&format!("day{day:02}.txt"))?,
+        };
 
         Self::new_from_file(file)
     }
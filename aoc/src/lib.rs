@@ -1,2 +1,5 @@
+pub mod fetch;
 pub mod gif;
+pub mod graph;
 pub mod input;
+pub mod progress;
@@ -0,0 +1,271 @@
+use std::{
+    error::Error,
+    fmt,
+    io::{stderr, IsTerminal},
+    process::exit,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Target time between clock checks
+const CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Maximum number of counts between clock checks
+const MAX_STRIDE: u64 = 1 << 20;
+
+/// Delay before the first progress update is drawn
+const DRAW_DELAY: Duration = Duration::from_millis(500);
+
+/// Time between progress updates
+const DRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Cancellation token shared between a long running loop and whoever wants to stop it
+#[derive(Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    /// Creates a new token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the process wide token which is cancelled by Ctrl-C.
+    /// A second Ctrl-C exits immediately
+    pub fn ctrl_c() -> Self {
+        static CTRL_C: OnceLock<Cancel> = OnceLock::new();
+
+        CTRL_C
+            .get_or_init(|| {
+                let cancel = Cancel::new();
+                let handler_cancel = cancel.clone();
+
+                // Failure just leaves the token uncancellable
+                let _ = ctrlc::set_handler(move || {
+                    if handler_cancel.is_cancelled() {
+                        exit(130);
+                    }
+
+                    handler_cancel.cancel();
+                });
+
+                cancel
+            })
+            .clone()
+    }
+
+    /// Cancels the token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Error returned when a long running loop is cancelled
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Progress counter for long running loops.
+/// The clock and cancellation token are only checked every so many counts, with the
+/// stride adapting so checks happen roughly every 10ms. Progress is drawn on stderr
+/// when it is a terminal
+pub struct Progress {
+    label: String,
+    total: Option<u64>,
+    count: u64,
+    cancel: Option<Cancel>,
+    display: bool,
+    drawn: bool,
+    start: Instant,
+    stride: u64,
+    next_check: u64,
+    last_check: Instant,
+    next_draw: Instant,
+}
+
+impl Progress {
+    /// Creates a new progress counter with an optional total count
+    pub fn new(label: &str, total: Option<u64>) -> Self {
+        let start = Instant::now();
+
+        Self {
+            label: label.to_string(),
+            total,
+            count: 0,
+            cancel: None,
+            display: stderr().is_terminal(),
+            drawn: false,
+            start,
+            stride: 1,
+            next_check: 1,
+            last_check: start,
+            next_draw: start + DRAW_DELAY,
+        }
+    }
+
+    /// Stops the count when the given token is cancelled
+    pub fn cancel_on(mut self, cancel: Cancel) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Never draws progress
+    pub fn hidden(mut self) -> Self {
+        self.display = false;
+        self
+    }
+
+    /// Returns the current count
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Adds to the count. Returns an error if the count has been cancelled
+    #[inline]
+    pub fn inc(&mut self, n: u64) -> Result<(), Cancelled> {
+        self.count += n;
+
+        if self.count >= self.next_check {
+            self.check()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the count. Returns an error if the count has been cancelled
+    #[inline]
+    pub fn set(&mut self, count: u64) -> Result<(), Cancelled> {
+        self.count = count;
+
+        if self.count >= self.next_check {
+            self.check()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Clears any progress drawn on the terminal
+    pub fn finish(&mut self) {
+        if self.drawn {
+            eprint!("\r\x1b[K");
+            self.drawn = false;
+        }
+    }
+
+    fn check(&mut self) -> Result<(), Cancelled> {
+        if let Some(cancel) = &self.cancel {
+            if cancel.is_cancelled() {
+                self.finish();
+                return Err(Cancelled);
+            }
+        }
+
+        let now = Instant::now();
+
+        // Adjust the stride to aim for the check interval
+        let elapsed = now - self.last_check;
+
+        if elapsed < CHECK_INTERVAL / 2 {
+            self.stride = (self.stride * 2).min(MAX_STRIDE);
+        } else if elapsed > CHECK_INTERVAL * 2 {
+            self.stride = (self.stride / 2).max(1);
+        }
+
+        self.last_check = now;
+        self.next_check = self.count + self.stride;
+
+        // Draw progress
+        if self.display && now >= self.next_draw {
+            self.draw(now);
+            self.next_draw = now + DRAW_INTERVAL;
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, now: Instant) {
+        let secs = (now - self.start).as_secs_f64();
+        let rate = self.count as f64 / secs;
+
+        match self.total {
+            Some(total) => eprint!(
+                "\r\x1b[K{}: {}/{} ({:.1}%, {:.0}/s)",
+                self.label,
+                self.count,
+                total,
+                (self.count as f64 * 100.0) / total as f64,
+                rate
+            ),
+            None => eprint!("\r\x1b[K{}: {} ({:.0}/s)", self.label, self.count, rate),
+        }
+
+        self.drawn = true;
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        let mut progress = Progress::new("Test", Some(1_000_000)).hidden();
+
+        for _ in 0..1_000_000 {
+            progress.inc(1).unwrap();
+        }
+
+        assert_eq!(progress.count(), 1_000_000);
+        assert!(progress.stride > 1);
+
+        progress.set(10).unwrap();
+        assert_eq!(progress.count(), 10);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let cancel = Cancel::new();
+        let mut progress = Progress::new("Test", None)
+            .hidden()
+            .cancel_on(cancel.clone());
+
+        for _ in 0..1000 {
+            progress.inc(1).unwrap();
+        }
+
+        cancel.cancel();
+
+        let mut iterations = 0;
+
+        let result = loop {
+            iterations += 1;
+
+            if let Err(e) = progress.inc(1) {
+                break e;
+            }
+        };
+
+        assert_eq!(result, Cancelled);
+        assert!(iterations <= MAX_STRIDE);
+    }
+}
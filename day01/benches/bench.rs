@@ -0,0 +1,27 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_line;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, part1, part2};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day01");
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_line(1, input_transform).unwrap())
+    });
+
+    let input = parse_input_line(1, input_transform).unwrap();
+
+    group.bench_function("part1", |b| b.iter(|| part1(&input)));
+    group.bench_function("part2", |b| b.iter(|| part2(&input)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
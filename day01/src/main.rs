@@ -1,19 +1,24 @@
 use std::error::Error;
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_line(1, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[u8]) -> u64 {
+pub fn part1(input: &[u8]) -> u64 {
     input
         .iter()
         .fold((input[input.len() - 1], 0), |(last, sum), next| {
@@ -29,7 +34,7 @@ fn part1(input: &[u8]) -> u64 {
         .1
 }
 
-fn part2(input: &[u8]) -> u64 {
+pub fn part2(input: &[u8]) -> u64 {
     input
         .iter()
         .enumerate()
@@ -47,7 +52,7 @@ fn part2(input: &[u8]) -> u64 {
 
 // Input parsing
 
-fn input_transform(line: String) -> Vec<u8> {
+pub fn input_transform(line: String) -> Vec<u8> {
     line.chars().map(|c| c as u8 - b'0').collect()
 }
 
@@ -2,20 +2,25 @@ use std::error::Error;
 
 use itertools::Itertools;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(2, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[InputEnt]) -> u64 {
+pub fn part1(input: &[InputEnt]) -> u64 {
     input
         .iter()
         .map(|line| {
@@ -27,7 +32,7 @@ fn part1(input: &[InputEnt]) -> u64 {
         .sum()
 }
 
-fn part2(input: &[InputEnt]) -> u64 {
+pub fn part2(input: &[InputEnt]) -> u64 {
     input
         .iter()
         .map(|line| {
@@ -47,9 +52,9 @@ fn part2(input: &[InputEnt]) -> u64 {
 
 // Input parsing
 
-type InputEnt = Vec<u64>;
+pub type InputEnt = Vec<u64>;
 
-fn input_transform(line: String) -> InputEnt {
+pub fn input_transform(line: String) -> InputEnt {
     line.split_whitespace()
         .map(|s| s.parse::<u64>().expect("Invalid number"))
         .collect()
@@ -1,25 +1,30 @@
 use std::{collections::HashMap, error::Error};
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_line(3, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(input));
-    println!("Part 2: {}", part2(input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(input));
+    }
 
     Ok(())
 }
 
-fn part1(target: u64) -> u64 {
+pub fn part1(target: u64) -> u64 {
     let (x, y) = spiral_to(target);
 
     x.unsigned_abs() + y.unsigned_abs()
 }
 
-fn part2(target: u64) -> u64 {
+pub fn part2(target: u64) -> u64 {
     spiral_load(target)
 }
 
@@ -123,7 +128,7 @@ fn spiral_load(target: u64) -> u64 {
 
 // Input parsing
 
-fn input_transform(line: String) -> u64 {
+pub fn input_transform(line: String) -> u64 {
     line.parse::<u64>().expect("Invalid number")
 }
 
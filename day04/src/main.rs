@@ -1,19 +1,24 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(4, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[InputEnt]) -> usize {
+pub fn part1(input: &[InputEnt]) -> usize {
     input
         .iter()
         .filter(|words| {
@@ -25,7 +30,7 @@ fn part1(input: &[InputEnt]) -> usize {
         .count()
 }
 
-fn part2(input: &[InputEnt]) -> usize {
+pub fn part2(input: &[InputEnt]) -> usize {
     let sorted = input
         .iter()
         .map(|words| {
@@ -45,9 +50,9 @@ fn part2(input: &[InputEnt]) -> usize {
 
 // Input parsing
 
-type InputEnt = Vec<String>;
+pub type InputEnt = Vec<String>;
 
-fn input_transform(line: String) -> InputEnt {
+pub fn input_transform(line: String) -> InputEnt {
     line.split_whitespace()
         .map(String::from)
         .collect::<Vec<_>>()
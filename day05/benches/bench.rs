@@ -0,0 +1,34 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use aoc::input::parse_input_vec;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, part1, part2};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day05");
+
+    // Long running parts
+    group.sample_size(10);
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(5, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(5, input_transform).unwrap();
+
+    group.bench_function("part1", |b| {
+        b.iter_batched(|| input.clone(), part1, BatchSize::SmallInput)
+    });
+    group.bench_function("part2", |b| {
+        b.iter_batched(|| input.clone(), part2, BatchSize::SmallInput)
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
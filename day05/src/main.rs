@@ -1,19 +1,24 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(5, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(input.clone()));
-    println!("Part 2: {}", part2(input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(input.clone()));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(input));
+    }
 
     Ok(())
 }
 
-fn part1(mut input: Vec<i64>) -> u64 {
+pub fn part1(mut input: Vec<i64>) -> u64 {
     let mut ip = 0;
     let mut count = 0;
 
@@ -31,7 +36,7 @@ fn part1(mut input: Vec<i64>) -> u64 {
     count
 }
 
-fn part2(mut input: Vec<i64>) -> u64 {
+pub fn part2(mut input: Vec<i64>) -> u64 {
     let mut ip = 0;
     let mut count = 0;
 
@@ -57,7 +62,7 @@ fn part2(mut input: Vec<i64>) -> u64 {
 
 // Input parsing
 
-fn input_transform(line: String) -> i64 {
+pub fn input_transform(line: String) -> i64 {
     line.parse::<i64>().expect("Invalid number")
 }
 
@@ -0,0 +1,28 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use aoc::input::parse_input_line;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, run};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day06");
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_line(6, input_transform).unwrap())
+    });
+
+    let input = parse_input_line(6, input_transform).unwrap();
+
+    group.bench_function("run", |b| {
+        b.iter_batched(|| input.clone(), run, BatchSize::SmallInput)
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
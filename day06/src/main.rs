@@ -1,6 +1,6 @@
 use std::{collections::HashSet, error::Error};
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
@@ -9,13 +9,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     let (part1, part2) = run(input);
 
     // Run parts
-    println!("Part 1: {}", part1);
-    println!("Part 2: {}", part2);
+    if run_part(1) {
+        println!("Part 1: {}", part1);
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2);
+    }
 
     Ok(())
 }
 
-fn run(mut banks: Vec<u64>) -> (u64, u64) {
+pub fn run(mut banks: Vec<u64>) -> (u64, u64) {
     let mut states = HashSet::new();
 
     let mut count = 0;
@@ -67,7 +72,7 @@ fn run(mut banks: Vec<u64>) -> (u64, u64) {
 
 // Input parsing
 
-fn input_transform(line: String) -> Vec<u64> {
+pub fn input_transform(line: String) -> Vec<u64> {
     line.split_whitespace()
         .map(|s| s.parse::<u64>().expect("Invalid number"))
         .collect()
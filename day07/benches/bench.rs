@@ -0,0 +1,31 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_vec;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, part1, part2, Programs};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day07");
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(7, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(7, input_transform).unwrap();
+
+    group.bench_function("Programs::new", |b| b.iter(|| Programs::new(&input)));
+
+    let programs = Programs::new(&input).unwrap();
+
+    group.bench_function("part1", |b| b.iter(|| part1(&programs).len()));
+    group.bench_function("part2", |b| b.iter(|| part2(&programs)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,343 @@
+use std::fmt;
+
+use super::Programs;
+
+/// Weights of a child of an unbalanced program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Child {
+    pub name: String,
+    pub weight: u64,
+    /// Weight of the child and everything it holds up
+    pub tot_weight: u64,
+}
+
+/// What can be done about an unbalanced program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// One child's total differs from the others and changing its weight balances
+    /// the program
+    Fix { child: String, weight: u64 },
+    /// One child's total differs from the others but it would need a negative weight
+    /// to balance the program
+    Negative { child: String },
+    /// Two children with different totals, either of which could be wrong
+    Tie,
+    /// More than two different totals, or several children with the odd one out
+    Ambiguous { totals: usize },
+}
+
+/// Program whose children don't all have the same total weight
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unbalanced {
+    pub name: String,
+    /// Distance from the root
+    pub depth: usize,
+    pub children: Vec<Child>,
+    pub diagnosis: Diagnosis,
+    /// True if nothing held up by this program is unbalanced, so it is where a wrong
+    /// weight is
+    pub cause: bool,
+}
+
+/// All of the unbalanced programs in a tower
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Unbalanced programs in depth first order from the root
+    pub unbalanced: Vec<Unbalanced>,
+}
+
+impl Report {
+    /// Walks the whole tower finding unbalanced programs
+    pub fn new(programs: &Programs) -> Self {
+        let mut unbalanced = Vec::new();
+
+        // Programs to visit with their depth and nearest unbalanced ancestor
+        let mut stack = vec![(programs.root.as_str(), 0, None)];
+
+        while let Some((name, depth, ancestor)) = stack.pop() {
+            let program = &programs.programs[name];
+
+            let children = program
+                .children
+                .iter()
+                .map(|c| {
+                    let child = &programs.programs[c];
+
+                    Child {
+                        name: child.name.clone(),
+                        weight: child.weight,
+                        tot_weight: child.tot_weight,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let mut ancestor = ancestor;
+
+            if let Some(diagnosis) = diagnose(&children) {
+                if let Some(a) = ancestor {
+                    let entry: &mut Unbalanced = &mut unbalanced[a];
+                    entry.cause = false;
+                }
+
+                ancestor = Some(unbalanced.len());
+
+                unbalanced.push(Unbalanced {
+                    name: name.to_string(),
+                    depth,
+                    children,
+                    diagnosis,
+                    cause: true,
+                });
+            }
+
+            // Visit children in order
+            for child in program.children.iter().rev() {
+                stack.push((child, depth + 1, ancestor));
+            }
+        }
+
+        Self { unbalanced }
+    }
+
+    /// Returns the unbalanced programs which are where a wrong weight is
+    pub fn causes(&self) -> impl Iterator<Item = &Unbalanced> {
+        self.unbalanced.iter().filter(|u| u.cause)
+    }
+
+    /// Returns the corrected weight if there is exactly one wrong weight and it can
+    /// be worked out
+    pub fn fix(&self) -> Result<u64, String> {
+        let causes = self.causes().collect::<Vec<_>>();
+
+        let cause = match causes.as_slice() {
+            [] => Err("The tower is balanced")?,
+            [cause] => cause,
+            _ => Err(format!(
+                "More than one weight is wrong, under {}",
+                causes
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))?,
+        };
+
+        match &cause.diagnosis {
+            Diagnosis::Fix { weight, .. } => Ok(*weight),
+            Diagnosis::Negative { child } => Err(format!(
+                "{child} under {} would need a negative weight",
+                cause.name
+            )),
+            Diagnosis::Tie => Err(format!(
+                "{} has two children with different weights and either could be wrong",
+                cause.name
+            )),
+            Diagnosis::Ambiguous { totals } => Err(format!(
+                "{} has children with {totals} different total weights",
+                cause.name
+            )),
+        }
+    }
+}
+
+/// Works out what is wrong with a program from its children's weights, returning
+/// None if it is balanced
+fn diagnose(children: &[Child]) -> Option<Diagnosis> {
+    // Count children with each total weight
+    let mut counts: Vec<(u64, usize)> = Vec::new();
+
+    for child in children {
+        match counts.iter_mut().find(|(w, _)| *w == child.tot_weight) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((child.tot_weight, 1)),
+        }
+    }
+
+    let diagnosis = match counts.as_slice() {
+        [] | [_] => None?,
+        [_, _] if children.len() == 2 => Diagnosis::Tie,
+        [(w1, 1), (w2, _)] | [(w2, _), (w1, 1)] => {
+            let child = children
+                .iter()
+                .find(|c| c.tot_weight == *w1)
+                .expect("Child with odd weight exists");
+
+            // Weights can be above i64::MAX
+            let adjusted = child.weight as i128 + (*w2 as i128 - *w1 as i128);
+
+            match u64::try_from(adjusted) {
+                Ok(weight) => Diagnosis::Fix {
+                    child: child.name.clone(),
+                    weight,
+                },
+                Err(_) => Diagnosis::Negative {
+                    child: child.name.clone(),
+                },
+            }
+        }
+        _ => Diagnosis::Ambiguous {
+            totals: counts.len(),
+        },
+    };
+
+    Some(diagnosis)
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unbalanced.is_empty() {
+            return writeln!(f, "The tower is balanced");
+        }
+
+        for u in &self.unbalanced {
+            let indent = "  ".repeat(u.depth);
+
+            writeln!(
+                f,
+                "{indent}{}{} is unbalanced:",
+                u.name,
+                if u.cause { " (cause)" } else { "" }
+            )?;
+
+            for c in &u.children {
+                writeln!(
+                    f,
+                    "{indent}  {} weighs {}, {} in total",
+                    c.name, c.weight, c.tot_weight
+                )?;
+            }
+
+            match &u.diagnosis {
+                Diagnosis::Fix { child, weight } => {
+                    writeln!(f, "{indent}  {child} should weigh {weight}")?
+                }
+                Diagnosis::Negative { child } => {
+                    writeln!(f, "{indent}  {child} would need a negative weight")?
+                }
+                Diagnosis::Tie => writeln!(f, "{indent}  Either child could be wrong")?,
+                Diagnosis::Ambiguous { totals } => writeln!(
+                    f,
+                    "{indent}  {totals} different totals, can't tell which is wrong"
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc::input::parse_test_vec;
+
+    use crate::input_transform;
+
+    use super::*;
+
+    fn programs(listing: &str) -> Programs {
+        Programs::new(&parse_test_vec(listing, input_transform).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_report() {
+        // d is too heavy by 2, which unbalances b and the root
+        let report = Report::new(&programs(
+            "root (1) -> a, b, c
+a (10)
+b (4) -> d, e, f
+c (10)
+d (4)
+e (2)
+f (2)
+",
+        ));
+
+        assert_eq!(report.unbalanced.len(), 2);
+        assert_eq!(report.unbalanced[0].name, "root");
+        assert!(!report.unbalanced[0].cause);
+        assert_eq!(
+            report.unbalanced[0].diagnosis,
+            Diagnosis::Fix {
+                child: "b".to_string(),
+                weight: 2
+            }
+        );
+        assert_eq!(report.causes().map(|u| &u.name).collect::<Vec<_>>(), ["b"]);
+        assert_eq!(report.fix(), Ok(2));
+
+        assert_eq!(
+            report.to_string(),
+            "root is unbalanced:
+  a weighs 10, 10 in total
+  b weighs 4, 12 in total
+  c weighs 10, 10 in total
+  b should weigh 2
+  b (cause) is unbalanced:
+    d weighs 4, 4 in total
+    e weighs 2, 2 in total
+    f weighs 2, 2 in total
+    d should weigh 2
+"
+        );
+    }
+
+    #[test]
+    fn test_problems() {
+        let balanced = programs("root (1) -> a, b\na (3)\nb (3)");
+
+        assert_eq!(
+            Report::new(&balanced).fix(),
+            Err("The tower is balanced".to_string())
+        );
+
+        let tie = programs("root (1) -> a, b\na (3)\nb (4)");
+
+        assert_eq!(
+            Report::new(&tie).fix(),
+            Err(
+                "root has two children with different weights and either could be wrong"
+                    .to_string()
+            )
+        );
+
+        let three = programs("root (1) -> a, b, c\na (3)\nb (4)\nc (5)");
+
+        assert_eq!(
+            Report::new(&three).fix(),
+            Err("root has children with 3 different total weights".to_string())
+        );
+
+        let negative = programs("root (1) -> a, b, c\na (1) -> d\nb (1)\nc (1)\nd (3)");
+
+        assert_eq!(
+            Report::new(&negative).fix(),
+            Err("a under root would need a negative weight".to_string())
+        );
+
+        let two = programs(
+            "root (1) -> a, b
+a (1) -> c, d, e
+b (1) -> f, g, h
+c (1)
+d (1)
+e (2)
+f (1)
+g (1)
+h (2)",
+        );
+
+        assert_eq!(
+            Report::new(&two).fix(),
+            Err("More than one weight is wrong, under a, b".to_string())
+        );
+
+        // The difference between the totals doesn't fit in an i64
+        let heavy = programs(&format!(
+            "root (1) -> a, b, c\na ({0})\nb ({1})\nc ({0})",
+            1u64 << 61,
+            (1u64 << 63) + 4
+        ));
+
+        assert_eq!(Report::new(&heavy).fix(), Ok(1 << 61));
+    }
+}
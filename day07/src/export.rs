@@ -0,0 +1,286 @@
+use std::{collections::HashSet, fmt::Write};
+
+use super::{balance::Report, Program, Programs};
+
+/// Returns the programs in depth first order from the root with their depth
+fn walk(programs: &Programs) -> Vec<(&Program, usize)> {
+    let mut order = Vec::with_capacity(programs.programs.len());
+    let mut stack = vec![(programs.root.as_str(), 0)];
+
+    while let Some((name, depth)) = stack.pop() {
+        let program = &programs.programs[name];
+
+        order.push((program, depth));
+
+        for child in program.children.iter().rev() {
+            stack.push((child, depth + 1));
+        }
+    }
+
+    order
+}
+
+/// Returns the tower as an indented tree with the weight and total weight of each
+/// program. Unbalanced programs are marked with an asterisk
+pub fn tree(programs: &Programs) -> String {
+    let report = Report::new(programs);
+    let unbalanced = report
+        .unbalanced
+        .iter()
+        .map(|u| u.name.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut out = String::new();
+
+    // Programs to draw with the prefix for the line and the prefix for their children
+    let mut stack = vec![(programs.root.as_str(), String::new(), String::new())];
+
+    while let Some((name, prefix, child_prefix)) = stack.pop() {
+        let program = &programs.programs[name];
+        let mark = if unbalanced.contains(name) { " *" } else { "" };
+
+        writeln!(
+            out,
+            "{prefix}{name} ({}) {}{mark}",
+            program.weight, program.tot_weight
+        )
+        .unwrap();
+
+        for (i, child) in program.children.iter().enumerate().rev() {
+            let (branch, next) = if i == program.children.len() - 1 {
+                ("`-- ", "    ")
+            } else {
+                ("+-- ", "|   ")
+            };
+
+            stack.push((
+                child,
+                format!("{child_prefix}{branch}"),
+                format!("{child_prefix}{next}"),
+            ));
+        }
+    }
+
+    out
+}
+
+/// Returns the tower in Graphviz DOT format. Unbalanced programs are filled orange,
+/// or red where the wrong weight is
+pub fn dot(programs: &Programs) -> String {
+    let report = Report::new(programs);
+
+    let mut out = String::new();
+
+    writeln!(out, "digraph tower {{").unwrap();
+    writeln!(out, "    rankdir=BT;").unwrap();
+    writeln!(out, "    node [shape=box];").unwrap();
+
+    for (program, _) in walk(programs) {
+        let style = match report.unbalanced.iter().find(|u| u.name == program.name) {
+            Some(u) if u.cause => " style=filled fillcolor=red",
+            Some(_) => " style=filled fillcolor=orange",
+            None => "",
+        };
+
+        let name = dot_escape(&program.name);
+
+        writeln!(
+            out,
+            "    \"{name}\" [label=\"{name}\\n{} / {}\"{style}];",
+            program.weight, program.tot_weight
+        )
+        .unwrap();
+
+        for child in &program.children {
+            writeln!(out, "    \"{}\" -> \"{name}\";", dot_escape(child)).unwrap();
+        }
+    }
+
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+/// Returns the tower as JSON, with the root and a flat list of programs in depth
+/// first order with a line for each
+pub fn json(programs: &Programs) -> String {
+    let report = Report::new(programs);
+    let unbalanced = report
+        .unbalanced
+        .iter()
+        .map(|u| u.name.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut out = String::new();
+
+    writeln!(out, "{{").unwrap();
+    writeln!(out, "  \"root\": {},", string(&programs.root)).unwrap();
+    writeln!(out, "  \"programs\": [").unwrap();
+
+    let order = walk(programs);
+
+    for (i, (program, depth)) in order.iter().enumerate() {
+        let parent = if program.parent.is_empty() {
+            "null".to_string()
+        } else {
+            string(&program.parent)
+        };
+
+        let children = program
+            .children
+            .iter()
+            .map(|c| string(c))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(
+            out,
+            "    {{\"name\": {}, \"weight\": {}, \"total\": {}, \"depth\": {depth}, \
+             \"parent\": {parent}, \"children\": [{children}], \"balanced\": {}}}{}",
+            string(&program.name),
+            program.weight,
+            program.tot_weight,
+            !unbalanced.contains(program.name.as_str()),
+            if i + 1 < order.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "  ]").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+/// Returns a quoted and escaped JSON string
+fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+
+    out
+}
+
+/// Escapes a name for use in a quoted DOT identifier or label
+fn dot_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc::input::parse_test_vec;
+
+    use crate::input_transform;
+
+    use super::*;
+
+    const TOWER: &str = "root (1) -> a, b, c
+a (10)
+b (4) -> d, e, f
+c (10)
+d (4)
+e (2)
+f (2)";
+
+    fn programs() -> Programs {
+        Programs::new(&parse_test_vec(TOWER, input_transform).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_tree() {
+        assert_eq!(
+            tree(&programs()),
+            "root (1) 33 *
++-- a (10) 10
++-- b (4) 12 *
+|   +-- d (4) 4
+|   +-- e (2) 2
+|   `-- f (2) 2
+`-- c (10) 10
+"
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        assert_eq!(
+            dot(&programs()),
+            r#"digraph tower {
+    rankdir=BT;
+    node [shape=box];
+    "root" [label="root\n1 / 33" style=filled fillcolor=orange];
+    "a" -> "root";
+    "b" -> "root";
+    "c" -> "root";
+    "a" [label="a\n10 / 10"];
+    "b" [label="b\n4 / 12" style=filled fillcolor=red];
+    "d" -> "b";
+    "e" -> "b";
+    "f" -> "b";
+    "d" [label="d\n4 / 4"];
+    "e" [label="e\n2 / 2"];
+    "f" [label="f\n2 / 2"];
+    "c" [label="c\n10 / 10"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_dot_escape() {
+        let input = parse_test_vec("q\"t (1) -> b\\s\nb\\s (2)", input_transform).unwrap();
+        let programs = Programs::new(&input).unwrap();
+
+        assert_eq!(
+            dot(&programs),
+            r#"digraph tower {
+    rankdir=BT;
+    node [shape=box];
+    "q\"t" [label="q\"t\n1 / 3"];
+    "b\\s" -> "q\"t";
+    "b\\s" [label="b\\s\n2 / 2"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json = json(&programs());
+        let lines = json.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[1], r#"  "root": "root","#);
+        assert_eq!(
+            lines[3],
+            r#"    {"name": "root", "weight": 1, "total": 33, "depth": 0, "parent": null, "children": ["a", "b", "c"], "balanced": false},"#
+        );
+        assert_eq!(
+            lines[9],
+            r#"    {"name": "c", "weight": 10, "total": 10, "depth": 1, "parent": "root", "children": [], "balanced": true}"#
+        );
+
+        assert_eq!(string("a\"b\\c\n"), r#""a\"b\\c\u000a""#);
+    }
+}
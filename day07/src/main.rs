@@ -1,171 +1,198 @@
-use std::{collections::HashMap, error::Error};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    env,
+    error::Error,
+    fs,
+};
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
+
+mod balance;
+mod export;
+
+use balance::Report;
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(7, input_transform)?;
 
-    let programs = Programs::new(&input);
+    let programs = Programs::new(&input)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&programs));
-    println!("Part 2: {}", part2(&programs));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&programs));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&programs)?);
+    }
+
+    // Export the tower if asked for
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let (export, file) = match args.as_slice() {
+        [] => return Ok(()),
+        [flag, file] if flag == "--tree" => (export::tree(&programs), file),
+        [flag, file] if flag == "--dot" => (export::dot(&programs), file),
+        [flag, file] if flag == "--json" => (export::json(&programs), file),
+        _ => Err("Usage: day07 [--tree | --dot | --json <file>]")?,
+    };
+
+    fs::write(file, export)?;
+    println!("Tower written to {file}");
 
     Ok(())
 }
 
-fn part1(programs: &Programs) -> &String {
+pub fn part1(programs: &Programs) -> &String {
     &programs.root
 }
 
-fn part2(programs: &Programs) -> u64 {
-    programs.find_unbalanced().unwrap()
+pub fn part2(programs: &Programs) -> Result<u64, String> {
+    Report::new(programs).fix()
 }
 
-struct Programs {
+pub struct Programs {
     programs: HashMap<String, Program>,
     root: String,
 }
 
 impl Programs {
-    fn new(input: &[Program]) -> Self {
+    /// Builds the tower, checking that every program held up exists, that each is
+    /// held up by at most one other, and that they form a single tree
+    pub fn new(input: &[Program]) -> Result<Self, String> {
+        if input.is_empty() {
+            Err("No programs")?
+        }
+
         // Create hash map
-        let mut programs: HashMap<String, Program> =
-            input.iter().map(|p| (p.name.clone(), p.clone())).collect();
+        let mut programs = HashMap::with_capacity(input.len());
+        let mut duplicates = BTreeSet::new();
 
-        // Calculate parentage
-        let parents = programs
-            .values()
-            .flat_map(|p| p.children.iter().map(|c| (p.name.clone(), c.clone())))
-            .collect::<Vec<_>>();
+        for p in input {
+            if programs.insert(p.name.clone(), p.clone()).is_some() {
+                duplicates.insert(p.name.as_str());
+            }
+        }
+
+        if !duplicates.is_empty() {
+            Err(format!("Duplicate programs: {}", list(duplicates)))?
+        }
 
         // Apply parents
-        for (parent, child) in parents {
-            programs
-                .get_mut(&child)
-                .expect("Child node not found")
-                .parent = parent;
+        let mut missing = BTreeSet::new();
+        let mut shared = BTreeSet::new();
+
+        for p in input {
+            for c in &p.children {
+                match programs.get_mut(c) {
+                    None => {
+                        missing.insert(format!("{c} (held up by {})", p.name));
+                    }
+                    Some(child) if !child.parent.is_empty() => {
+                        shared.insert(format!("{c} (held up by {} and {})", child.parent, p.name));
+                    }
+                    Some(child) => child.parent = p.name.clone(),
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            Err(format!("Missing programs: {}", list(missing)))?
+        }
+
+        if !shared.is_empty() {
+            Err(format!("Programs held up more than once: {}", list(shared)))?
         }
 
         // Find root node
-        let root = programs
+        let roots = programs
             .values()
-            .find_map(|p| {
-                if p.parent.is_empty() {
-                    Some(p.name.clone())
-                } else {
-                    None
-                }
-            })
-            .expect("Root not found");
+            .filter(|p| p.parent.is_empty())
+            .map(|p| p.name.as_str())
+            .collect::<BTreeSet<_>>();
 
-        // Calculate total weights
-        let mut tot_weights = HashMap::new();
+        if roots.len() > 1 {
+            Err(format!("Multiple roots: {}", list(roots.iter())))?
+        }
+
+        // Walk the tree from the root, which reaches everything unless there is a
+        // cycle
+        let mut order = Vec::with_capacity(programs.len());
+        let mut stack = roots.into_iter().collect::<Vec<_>>();
 
-        for p in programs.values() {
-            Self::calc_tot_weight(&programs, &p.name, &mut tot_weights);
+        while let Some(name) = stack.pop() {
+            order.push(name.to_string());
+            stack.extend(programs[name].children.iter().map(|c| c.as_str()));
         }
 
-        // Apply total weights
-        for (n, v) in tot_weights {
-            programs.get_mut(&n).unwrap().tot_weight = v;
+        if order.len() < programs.len() {
+            Err(format!(
+                "Programs hold each other up: {}",
+                list(Self::cycle(&programs, &order))
+            ))?
         }
 
-        Programs { programs, root }
-    }
+        // Calculate total weights, children first
+        for name in order.iter().rev() {
+            let tot_weight = programs[name].weight
+                + programs[name]
+                    .children
+                    .iter()
+                    .map(|c| programs[c].tot_weight)
+                    .sum::<u64>();
 
-    fn calc_tot_weight(
-        programs: &HashMap<String, Program>,
-        name: &str,
-        tot_weights: &mut HashMap<String, u64>,
-    ) -> u64 {
-        match tot_weights.get(name) {
-            None => {
-                let tw = programs[name].weight
-                    + programs[name]
-                        .children
-                        .iter()
-                        .map(|c| Self::calc_tot_weight(programs, c, tot_weights))
-                        .sum::<u64>();
-
-                tot_weights.insert(name.to_string(), tw);
-
-                tw
-            }
-            Some(v) => *v,
+            programs.get_mut(name).unwrap().tot_weight = tot_weight;
         }
-    }
 
-    fn find_unbalanced(&self) -> Option<u64> {
-        self.find_unbalanced_iter(&self.root)
+        let root = order.swap_remove(0);
+
+        Ok(Programs { programs, root })
     }
 
-    fn find_unbalanced_iter(&self, node: &str) -> Option<u64> {
-        let node = &self.programs[node];
+    /// Returns the names in a cycle of programs which aren't reachable from the root
+    fn cycle<'a>(programs: &'a HashMap<String, Program>, reached: &[String]) -> Vec<&'a str> {
+        let reached = reached.iter().map(|n| n.as_str()).collect::<HashSet<_>>();
 
-        // Build set of weight counts
-        let mut weight_set = HashMap::new();
+        // Every unreachable program has a parent, so following parents from one of
+        // them must end up going round a cycle
+        let start = programs
+            .keys()
+            .filter(|n| !reached.contains(n.as_str()))
+            .min()
+            .expect("Unreachable program exists");
 
-        for c in node.children.iter() {
-            let weight = &self.programs[c].tot_weight;
-            *weight_set.entry(*weight).or_insert(0) += 1usize;
-        }
+        let mut path = vec![start.as_str()];
 
-        match weight_set.len() {
-            0 => None,
-            1 | 2 => {
-                // Iterate children
-                for c in node.children.iter() {
-                    if let Some(v) = self.find_unbalanced_iter(c) {
-                        return Some(v);
-                    }
-                }
+        loop {
+            let parent = programs[*path.last().unwrap()].parent.as_str();
 
-                if weight_set.len() == 2 {
-                    // This node is incorrect
-                    let incorrect_weight = weight_set
-                        .iter()
-                        .find_map(|(weight, count)| if *count == 1 { Some(*weight) } else { None })
-                        .expect("Incorrect weight not found");
-
-                    let correct_weight = weight_set
-                        .iter()
-                        .find_map(|(weight, count)| if *count != 1 { Some(*weight) } else { None })
-                        .expect("Correct weight not found");
-
-                    let weight_adj = correct_weight as i64 - incorrect_weight as i64;
-
-                    // Which child has the incorrect weight?
-                    let incorrect_child = node
-                        .children
-                        .iter()
-                        .find_map(|c| {
-                            let program = &self.programs[c];
-
-                            if program.tot_weight == incorrect_weight {
-                                Some(program)
-                            } else {
-                                None
-                            }
-                        })
-                        .expect("Incorrect program not found");
-
-                    // Return corrected weight
-                    Some((incorrect_child.weight as i64 + weight_adj) as u64)
-                } else {
-                    None
-                }
+            if let Some(pos) = path.iter().position(|n| *n == parent) {
+                let mut cycle = path.split_off(pos);
+
+                cycle.sort_unstable();
+
+                return cycle;
             }
-            _ => panic!("Can't determine incorrect weight"),
+
+            path.push(parent);
         }
     }
 }
 
+/// Joins names into a comma separated list
+fn list<T: AsRef<str>>(names: impl IntoIterator<Item = T>) -> String {
+    names
+        .into_iter()
+        .map(|n| n.as_ref().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 // Input parsing
 
 #[derive(Debug, Clone)]
-struct Program {
+pub struct Program {
     name: String,
     weight: u64,
     tot_weight: u64,
@@ -173,7 +200,7 @@ struct Program {
     parent: String,
 }
 
-fn input_transform(line: String) -> Program {
+pub fn input_transform(line: String) -> Program {
     let mut iter = line.split_whitespace();
 
     let name = iter.next().expect("No program name").to_string();
@@ -226,9 +253,45 @@ cntj (57)
     fn test1() {
         let input = parse_test_vec(EXAMPLE1, input_transform).unwrap();
 
-        let programs = Programs::new(&input);
+        let programs = Programs::new(&input).unwrap();
 
         assert_eq!(part1(&programs), "tknk");
-        assert_eq!(part2(&programs), 60);
+        assert_eq!(part2(&programs), Ok(60));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let error = |listing: &str| {
+            let input = parse_test_vec(listing, input_transform).unwrap();
+
+            Programs::new(&input).err().unwrap()
+        };
+
+        assert_eq!(error(""), "No programs");
+        assert_eq!(
+            error("a (1) -> b\nb (2)\nb (3)\nc (1)\nc (1)"),
+            "Duplicate programs: b, c"
+        );
+        assert_eq!(
+            error("a (1) -> b, x\nb (2) -> y"),
+            "Missing programs: x (held up by a), y (held up by b)"
+        );
+        assert_eq!(
+            error("a (1) -> b, c\nb (1) -> c\nc (1)"),
+            "Programs held up more than once: c (held up by a and b)"
+        );
+        assert_eq!(
+            error("a (1) -> b\nb (1)\nc (1) -> d\nd (1)\ne (1)"),
+            "Multiple roots: a, c, e"
+        );
+        assert_eq!(
+            error("a (1) -> b\nb (1)\nx (1) -> y\ny (1) -> z\nz (1) -> x, c\nc (1)"),
+            "Programs hold each other up: x, y, z"
+        );
+        assert_eq!(
+            error("a (1) -> b\nb (1) -> a"),
+            "Programs hold each other up: a, b"
+        );
+        assert_eq!(error("a (1) -> a"), "Programs hold each other up: a");
     }
 }
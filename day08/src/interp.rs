@@ -0,0 +1,624 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use crate::trace::Trace;
+
+/// Register update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegOp {
+    Inc,
+    Dec,
+}
+
+/// Comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CondOp {
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl CondOp {
+    fn eval(&self, a: i64, b: i64) -> bool {
+        match self {
+            CondOp::Lt => a < b,
+            CondOp::Gt => a > b,
+            CondOp::Le => a <= b,
+            CondOp::Ge => a >= b,
+            CondOp::Eq => a == b,
+            CondOp::Ne => a != b,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            CondOp::Lt => "<",
+            CondOp::Gt => ">",
+            CondOp::Le => "<=",
+            CondOp::Ge => ">=",
+            CondOp::Eq => "==",
+            CondOp::Ne => "!=",
+        }
+    }
+}
+
+/// Register name or immediate value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Reg(String),
+    Imm(i64),
+}
+
+/// Condition guarding an instruction. and binds more tightly than or
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cond {
+    Cmp(Operand, CondOp, Operand),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+}
+
+/// What an instruction does when its condition passes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stmt {
+    /// Increases or decreases a register
+    Update {
+        reg: String,
+        op: RegOp,
+        amount: Operand,
+    },
+    /// Continues at a label
+    Jump(String),
+    /// Does nothing, for a line with only a label
+    Nop,
+}
+
+/// Line of a program: `[label:] stmt [if cond]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub label: Option<String>,
+    pub stmt: Stmt,
+    pub cond: Option<Cond>,
+}
+
+/// Program with its labels resolved to addresses
+pub struct Program<'a> {
+    instructions: &'a [Instruction],
+    labels: HashMap<&'a str, usize>,
+}
+
+impl<'a> Program<'a> {
+    /// Resolves the labels in a list of instructions
+    pub fn new(instructions: &'a [Instruction]) -> Result<Self, String> {
+        let mut labels = HashMap::new();
+
+        for (addr, instruction) in instructions.iter().enumerate() {
+            if let Some(label) = &instruction.label {
+                if labels.insert(label.as_str(), addr).is_some() {
+                    Err(format!("Duplicate label '{label}' at {addr}"))?
+                }
+            }
+        }
+
+        for (addr, instruction) in instructions.iter().enumerate() {
+            if let Stmt::Jump(label) = &instruction.stmt {
+                if !labels.contains_key(label.as_str()) {
+                    Err(format!("Unknown label '{label}' at {addr}"))?
+                }
+            }
+        }
+
+        Ok(Self {
+            instructions,
+            labels,
+        })
+    }
+
+    /// Returns the instructions
+    pub fn instructions(&self) -> &'a [Instruction] {
+        self.instructions
+    }
+
+    /// Returns the names of the registers used, in order
+    pub fn registers(&self) -> Vec<&'a str> {
+        let mut names = BTreeSet::new();
+
+        for instruction in self.instructions {
+            if let Stmt::Update { reg, amount, .. } = &instruction.stmt {
+                names.insert(reg.as_str());
+                amount.add_reg(&mut names);
+            }
+
+            if let Some(cond) = &instruction.cond {
+                cond.add_regs(&mut names);
+            }
+        }
+
+        names.into_iter().collect()
+    }
+}
+
+impl Operand {
+    fn add_reg<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        if let Operand::Reg(name) = self {
+            names.insert(name);
+        }
+    }
+}
+
+impl Cond {
+    fn add_regs<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        match self {
+            Cond::Cmp(a, _, b) => {
+                a.add_reg(names);
+                b.add_reg(names);
+            }
+            Cond::And(a, b) | Cond::Or(a, b) => {
+                a.add_regs(names);
+                b.add_regs(names);
+            }
+        }
+    }
+}
+
+/// Runs a program. Registers start at zero
+pub struct Machine<'a> {
+    program: &'a Program<'a>,
+    registers: HashMap<String, i64>,
+    ip: usize,
+    steps: u64,
+    highest: Option<i64>,
+    trace: Option<Trace<'a>>,
+}
+
+impl<'a> Machine<'a> {
+    /// Creates a machine at the start of a program
+    pub fn new(program: &'a Program<'a>) -> Self {
+        Self {
+            program,
+            registers: HashMap::new(),
+            ip: 0,
+            steps: 0,
+            highest: None,
+            trace: None,
+        }
+    }
+
+    /// Creates a machine which records every instruction executed
+    pub fn traced(program: &'a Program<'a>) -> Self {
+        Self {
+            trace: Some(Trace::new(program)),
+            ..Self::new(program)
+        }
+    }
+
+    /// Returns the execution trace if the machine is recording one
+    pub fn trace(&self) -> Option<&Trace<'a>> {
+        self.trace.as_ref()
+    }
+
+    /// Returns the value of a register
+    pub fn reg(&self, name: &str) -> i64 {
+        self.registers.get(name).copied().unwrap_or(0)
+    }
+
+    /// Returns the largest value in a register which has been written
+    pub fn largest(&self) -> Option<i64> {
+        self.registers.values().max().copied()
+    }
+
+    /// Returns the highest value written to a register
+    pub fn highest(&self) -> Option<i64> {
+        self.highest
+    }
+
+    /// Returns true if execution has moved past the last instruction
+    pub fn finished(&self) -> bool {
+        self.ip >= self.program.instructions.len()
+    }
+
+    /// Executes the next instruction, returning its address, or None if the program
+    /// has finished
+    pub fn step(&mut self) -> Result<Option<usize>, String> {
+        let addr = self.ip;
+
+        let Some(instruction) = self.program.instructions.get(addr) else {
+            return Ok(None);
+        };
+
+        let passed = match &instruction.cond {
+            Some(cond) => self.eval(cond),
+            None => true,
+        };
+
+        self.ip += 1;
+
+        let mut write = None;
+
+        if passed {
+            match &instruction.stmt {
+                Stmt::Update { reg, op, amount } => {
+                    let amount = self.value(amount);
+                    let current = self.reg(reg);
+
+                    let value = match op {
+                        RegOp::Inc => current.checked_add(amount),
+                        RegOp::Dec => current.checked_sub(amount),
+                    }
+                    .ok_or_else(|| format!("Overflow at instruction {addr}"))?;
+
+                    self.registers.insert(reg.clone(), value);
+                    self.highest = self.highest.max(Some(value));
+
+                    write = Some((reg.as_str(), value));
+                }
+                Stmt::Jump(label) => self.ip = self.program.labels[label.as_str()],
+                Stmt::Nop => (),
+            }
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.record(addr, passed, write);
+        }
+
+        self.steps += 1;
+
+        Ok(Some(addr))
+    }
+
+    /// Runs the program to the end, failing if it takes more than a number of steps
+    pub fn run(&mut self, max_steps: u64) -> Result<(), String> {
+        while !self.finished() {
+            if self.steps >= max_steps {
+                Err(format!(
+                    "Program did not finish within {max_steps} steps, at instruction {}",
+                    self.ip
+                ))?
+            }
+
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
+    fn eval(&self, cond: &Cond) -> bool {
+        match cond {
+            Cond::Cmp(a, op, b) => op.eval(self.value(a), self.value(b)),
+            Cond::And(a, b) => self.eval(a) && self.eval(b),
+            Cond::Or(a, b) => self.eval(a) || self.eval(b),
+        }
+    }
+
+    fn value(&self, operand: &Operand) -> i64 {
+        match operand {
+            Operand::Reg(name) => self.reg(name),
+            Operand::Imm(imm) => *imm,
+        }
+    }
+}
+
+// Parsing
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split_whitespace().collect::<Vec<_>>();
+
+        // Keywords are recognised by their position, so they can also be names
+        let (label, rest) = match tokens.split_first() {
+            Some((token, rest)) if token.ends_with(':') => {
+                (Some(name(&token[..token.len() - 1])?), rest)
+            }
+            _ => (None, &tokens[..]),
+        };
+
+        let (stmt, rest) = match rest {
+            [] if label.is_some() => (Stmt::Nop, rest),
+            [reg, op @ ("inc" | "dec"), amount, rest @ ..] if matches!(rest, [] | ["if", ..]) => (
+                Stmt::Update {
+                    reg: name(reg)?,
+                    op: if *op == "inc" { RegOp::Inc } else { RegOp::Dec },
+                    amount: operand(amount)?,
+                },
+                rest,
+            ),
+            ["jmp", label, rest @ ..] => (Stmt::Jump(name(label)?), rest),
+            _ => Err(format!("Invalid instruction '{s}'"))?,
+        };
+
+        let cond = match rest {
+            [] => None,
+            ["if", rest @ ..] => Some(parse_or(rest)?),
+            [token, ..] => Err(format!("Expecting 'if', found '{token}'"))?,
+        };
+
+        Ok(Self { label, stmt, cond })
+    }
+}
+
+/// Parses conditions separated by or. Comparisons are always three tokens, so the
+/// and and or between them are found by position
+fn parse_or(tokens: &[&str]) -> Result<Cond, String> {
+    let (mut cond, mut rest) = parse_and(tokens)?;
+
+    while let ["or", tail @ ..] = rest {
+        let (next, tail) = parse_and(tail)?;
+
+        cond = Cond::Or(Box::new(cond), Box::new(next));
+        rest = tail;
+    }
+
+    match rest {
+        [] => Ok(cond),
+        [token, ..] => Err(format!("Expecting 'and' or 'or', found '{token}'")),
+    }
+}
+
+/// Parses comparisons separated by and, returning the condition and the tokens after it
+fn parse_and<'a, 'b>(tokens: &'a [&'b str]) -> Result<(Cond, &'a [&'b str]), String> {
+    let (mut cond, mut rest) = parse_cmp(tokens)?;
+
+    while let ["and", tail @ ..] = rest {
+        let (next, tail) = parse_cmp(tail)?;
+
+        cond = Cond::And(Box::new(cond), Box::new(next));
+        rest = tail;
+    }
+
+    Ok((cond, rest))
+}
+
+/// Parses a comparison, returning it and the tokens after it
+fn parse_cmp<'a, 'b>(tokens: &'a [&'b str]) -> Result<(Cond, &'a [&'b str]), String> {
+    let [a, op, b, rest @ ..] = tokens else {
+        Err(format!("Invalid comparison '{}'", tokens.join(" ")))?
+    };
+
+    let op = match *op {
+        "<" => CondOp::Lt,
+        ">" => CondOp::Gt,
+        "<=" => CondOp::Le,
+        ">=" => CondOp::Ge,
+        "==" => CondOp::Eq,
+        "!=" => CondOp::Ne,
+        _ => Err(format!("Invalid condition op '{op}'"))?,
+    };
+
+    Ok((Cond::Cmp(operand(a)?, op, operand(b)?), rest))
+}
+
+fn operand(s: &str) -> Result<Operand, String> {
+    match s.parse::<i64>() {
+        Ok(imm) => Ok(Operand::Imm(imm)),
+        Err(_) => Ok(Operand::Reg(name(s)?)),
+    }
+}
+
+/// Checks a register or label name
+fn name(s: &str) -> Result<String, String> {
+    let valid = s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(format!("Invalid name '{s}'"))
+    }
+}
+
+// Display
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Reg(name) => write!(f, "{name}"),
+            Operand::Imm(imm) => write!(f, "{imm}"),
+        }
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cond::Cmp(a, op, b) => write!(f, "{a} {} {b}", op.symbol()),
+            Cond::And(a, b) => write!(f, "{a} and {b}"),
+            Cond::Or(a, b) => write!(f, "{a} or {b}"),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "{label}:")?;
+
+            if self.stmt != Stmt::Nop {
+                write!(f, " ")?;
+            }
+        }
+
+        match &self.stmt {
+            Stmt::Update { reg, op, amount } => match op {
+                RegOp::Inc => write!(f, "{reg} inc {amount}")?,
+                RegOp::Dec => write!(f, "{reg} dec {amount}")?,
+            },
+            Stmt::Jump(label) => write!(f, "jmp {label}")?,
+            Stmt::Nop => (),
+        }
+
+        if let Some(cond) = &self.cond {
+            write!(f, " if {cond}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(listing: &str) -> Vec<Instruction> {
+        listing.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let listing = "b inc 5 if a > 1
+loop: a dec b if b < 5 and c != 0 or a >= -2
+jmp loop if x <= y
+a inc 1
+end:";
+
+        let instructions = parse(listing);
+
+        assert_eq!(
+            instructions[1].cond,
+            Some(Cond::Or(
+                Box::new(Cond::And(
+                    Box::new(Cond::Cmp(
+                        Operand::Reg("b".to_string()),
+                        CondOp::Lt,
+                        Operand::Imm(5)
+                    )),
+                    Box::new(Cond::Cmp(
+                        Operand::Reg("c".to_string()),
+                        CondOp::Ne,
+                        Operand::Imm(0)
+                    ))
+                )),
+                Box::new(Cond::Cmp(
+                    Operand::Reg("a".to_string()),
+                    CondOp::Ge,
+                    Operand::Imm(-2)
+                ))
+            ))
+        );
+
+        // Round trip
+        let printed = instructions
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(printed, listing);
+
+        assert!("a inc".parse::<Instruction>().is_err());
+        assert!("a mul 2".parse::<Instruction>().is_err());
+        assert!("a inc 2 if".parse::<Instruction>().is_err());
+        assert!("a inc 2 if b > 1 and".parse::<Instruction>().is_err());
+        assert!("a inc 2 when b > 1".parse::<Instruction>().is_err());
+        assert!("a inc 2 if b =< 1".parse::<Instruction>().is_err());
+        assert!("1a inc 2".parse::<Instruction>().is_err());
+        assert!("a inc 2 if b > 1 c".parse::<Instruction>().is_err());
+    }
+
+    #[test]
+    fn test_keyword_names() {
+        let listing = "inc inc 5 if dec > 1
+if: jmp dec if and > 1 and or < 2 or if != 0
+jmp inc if
+dec dec jmp
+and:";
+
+        let instructions = parse(listing);
+
+        assert_eq!(
+            instructions[0].stmt,
+            Stmt::Update {
+                reg: "inc".to_string(),
+                op: RegOp::Inc,
+                amount: Operand::Imm(5)
+            }
+        );
+        assert_eq!(instructions[1].label, Some("if".to_string()));
+        assert_eq!(instructions[1].stmt, Stmt::Jump("dec".to_string()));
+        assert_eq!(
+            instructions[1].cond.as_ref().map(|c| c.to_string()),
+            Some("and > 1 and or < 2 or if != 0".to_string())
+        );
+        assert!(matches!(instructions[1].cond, Some(Cond::Or(..))));
+        assert_eq!(
+            instructions[2].stmt,
+            Stmt::Update {
+                reg: "jmp".to_string(),
+                op: RegOp::Inc,
+                amount: Operand::Reg("if".to_string())
+            }
+        );
+
+        // Round trip
+        let printed = instructions
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(printed, listing);
+    }
+
+    #[test]
+    fn test_run() {
+        // Multiply a by b into c
+        let instructions = parse(
+            "a inc 6
+b inc 7
+loop: jmp end if b <= 0
+c inc a
+b dec 1
+jmp loop
+end:",
+        );
+
+        let program = Program::new(&instructions).unwrap();
+        let mut machine = Machine::new(&program);
+
+        machine.run(1000).unwrap();
+
+        assert_eq!(machine.reg("c"), 42);
+        assert_eq!(machine.reg("b"), 0);
+        assert_eq!(machine.largest(), Some(42));
+        assert_eq!(machine.highest(), Some(42));
+
+        // Step limit
+        let mut machine = Machine::new(&program);
+
+        assert_eq!(
+            machine.run(10),
+            Err("Program did not finish within 10 steps, at instruction 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_program() {
+        let instructions = parse("jmp nowhere");
+
+        assert_eq!(
+            Program::new(&instructions).err(),
+            Some("Unknown label 'nowhere' at 0".to_string())
+        );
+
+        let instructions = parse("x: a inc 1\nx: a inc 2");
+
+        assert_eq!(
+            Program::new(&instructions).err(),
+            Some("Duplicate label 'x' at 1".to_string())
+        );
+
+        let instructions = parse("a inc 9223372036854775807\na inc a");
+        let program = Program::new(&instructions).unwrap();
+
+        assert_eq!(
+            Machine::new(&program).run(10),
+            Err("Overflow at instruction 1".to_string())
+        );
+    }
+}
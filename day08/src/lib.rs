@@ -0,0 +1,2 @@
+pub mod interp;
+pub mod trace;
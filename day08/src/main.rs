@@ -1,152 +1,80 @@
-use std::{cmp::max, collections::HashMap, error::Error};
+use std::{env, error::Error, fs};
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
+
+use day08::interp::{Instruction, Machine, Program};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let instructions = parse_input_vec(8, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&instructions));
-    println!("Part 2: {}", part2(&instructions));
-
-    Ok(())
-}
-
-fn part1(instructions: &[Instruction]) -> i64 {
-    let mut registers = HashMap::new();
+    if run_part(1) {
+        println!("Part 1: {}", part1(&instructions)?);
+    }
 
-    for i in instructions {
-        let cond_reg_val = registers.get(&i.cond_reg).unwrap_or(&0);
+    if run_part(2) {
+        println!("Part 2: {}", part2(&instructions)?);
+    }
 
-        let cond_passed = match i.cond_op {
-            CondOp::Lt => *cond_reg_val < i.cond_val,
-            CondOp::Gt => *cond_reg_val > i.cond_val,
-            CondOp::Le => *cond_reg_val <= i.cond_val,
-            CondOp::Ge => *cond_reg_val >= i.cond_val,
-            CondOp::Eq => *cond_reg_val == i.cond_val,
-            CondOp::Ne => *cond_reg_val != i.cond_val,
-        };
+    // Write the register history if asked for
+    let args = env::args().skip(1).collect::<Vec<_>>();
 
-        if cond_passed {
-            let reg = registers.entry(i.reg.clone()).or_insert(0);
+    match args.as_slice() {
+        [] => (),
+        [flag, file] if flag == "--trace" => {
+            let (csv, steps) = trace(&instructions)?;
 
-            match i.regop {
-                RegOp::Inc => *reg += i.regop_val,
-                RegOp::Dec => *reg -= i.regop_val,
-            }
+            fs::write(file, csv)?;
+            println!("Trace of {steps} steps written to {file}");
         }
+        _ => Err("Usage: day08 [--trace <csv file>]")?,
     }
 
-    *registers.values().max().expect("No registers")
+    Ok(())
 }
 
-fn part2(instructions: &[Instruction]) -> i64 {
-    let mut max_val = i64::MIN;
-    let mut registers = HashMap::new();
+/// Limit for programs with jumps which may never finish
+const MAX_STEPS: u64 = 100_000_000;
 
-    for i in instructions {
-        let cond_reg_val = registers.get(&i.cond_reg).unwrap_or(&0);
+pub fn part1(instructions: &[Instruction]) -> Result<i64, String> {
+    let program = Program::new(instructions)?;
+    let mut machine = Machine::new(&program);
 
-        let cond_passed = match i.cond_op {
-            CondOp::Lt => *cond_reg_val < i.cond_val,
-            CondOp::Gt => *cond_reg_val > i.cond_val,
-            CondOp::Le => *cond_reg_val <= i.cond_val,
-            CondOp::Ge => *cond_reg_val >= i.cond_val,
-            CondOp::Eq => *cond_reg_val == i.cond_val,
-            CondOp::Ne => *cond_reg_val != i.cond_val,
-        };
+    machine.run(MAX_STEPS)?;
 
-        if cond_passed {
-            let reg = registers.entry(i.reg.clone()).or_insert(0);
+    machine
+        .largest()
+        .ok_or_else(|| "No registers were written".to_string())
+}
 
-            match i.regop {
-                RegOp::Inc => *reg += i.regop_val,
-                RegOp::Dec => *reg -= i.regop_val,
-            }
+pub fn part2(instructions: &[Instruction]) -> Result<i64, String> {
+    let program = Program::new(instructions)?;
+    let mut machine = Machine::new(&program);
 
-            max_val = max(max_val, *reg);
-        }
-    }
+    machine.run(MAX_STEPS)?;
 
-    max_val
+    machine
+        .highest()
+        .ok_or_else(|| "No registers were written".to_string())
 }
 
-enum RegOp {
-    Inc,
-    Dec,
-}
+/// Returns the history of every register as CSV, and the number of steps executed
+pub fn trace(instructions: &[Instruction]) -> Result<(String, usize), String> {
+    let program = Program::new(instructions)?;
+    let mut machine = Machine::traced(&program);
 
-enum CondOp {
-    Lt,
-    Gt,
-    Le,
-    Ge,
-    Eq,
-    Ne,
-}
+    machine.run(MAX_STEPS)?;
+
+    let trace = machine.trace().expect("Machine is traced");
 
-struct Instruction {
-    reg: String,
-    regop: RegOp,
-    regop_val: i64,
-    cond_reg: String,
-    cond_op: CondOp,
-    cond_val: i64,
+    Ok((trace.csv(), trace.events().len()))
 }
 
 // Input parsing
 
-fn input_transform(line: String) -> Instruction {
-    let mut iter = line.split_whitespace();
-
-    let reg = iter.next().expect("Expecting register name").to_string();
-
-    let regop = match iter.next().expect("Expecting register op") {
-        "inc" => RegOp::Inc,
-        "dec" => RegOp::Dec,
-        _ => panic!("Invalid register op"),
-    };
-
-    let regop_val = iter
-        .next()
-        .expect("Expecting register op value")
-        .parse::<i64>()
-        .expect("Invalid register op value");
-
-    // Skip "if"
-    iter.next();
-
-    let cond_reg = iter
-        .next()
-        .expect("Expecting condition register name")
-        .to_string();
-
-    let cond_op = match iter.next().expect("Expecting condition op") {
-        "<" => CondOp::Lt,
-        ">" => CondOp::Gt,
-        "<=" => CondOp::Le,
-        ">=" => CondOp::Ge,
-        "==" => CondOp::Eq,
-        "!=" => CondOp::Ne,
-        _ => panic!("Invalid condition op"),
-    };
-
-    let cond_val = iter
-        .next()
-        .expect("Expecting condition value")
-        .parse::<i64>()
-        .expect("Invalid condition value");
-
-    // TODO
-    Instruction {
-        reg,
-        regop,
-        regop_val,
-        cond_reg,
-        cond_op,
-        cond_val,
-    }
+pub fn input_transform(line: String) -> Instruction {
+    line.parse().unwrap_or_else(|e| panic!("{e}"))
 }
 
 #[cfg(test)]
@@ -164,7 +92,7 @@ c inc -20 if c == 10
     #[test]
     fn test1() {
         let instructions = parse_test_vec(EXAMPLE1, input_transform).unwrap();
-        assert_eq!(part1(&instructions), 1);
-        assert_eq!(part2(&instructions), 10);
+        assert_eq!(part1(&instructions), Ok(1));
+        assert_eq!(part2(&instructions), Ok(10));
     }
 }
@@ -0,0 +1,153 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::interp::{Instruction, Program};
+
+/// Executed instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    /// Address of the instruction
+    pub addr: usize,
+    /// True if the instruction's condition passed or it has none
+    pub passed: bool,
+    /// Register index and value written
+    pub write: Option<(usize, i64)>,
+}
+
+/// Record of every instruction executed by a machine
+pub struct Trace<'a> {
+    instructions: &'a [Instruction],
+    registers: Vec<&'a str>,
+    index: HashMap<&'a str, usize>,
+    events: Vec<Event>,
+}
+
+impl<'a> Trace<'a> {
+    /// Creates an empty trace for a program
+    pub fn new(program: &Program<'a>) -> Self {
+        let registers = program.registers();
+
+        let index = registers
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (*name, i))
+            .collect();
+
+        Self {
+            instructions: program.instructions(),
+            registers,
+            index,
+            events: Vec::new(),
+        }
+    }
+
+    /// Records an executed instruction
+    pub fn record(&mut self, addr: usize, passed: bool, write: Option<(&str, i64)>) {
+        let write = write.map(|(name, value)| (self.index[name], value));
+
+        self.events.push(Event {
+            addr,
+            passed,
+            write,
+        });
+    }
+
+    /// Returns the executed instructions in order
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Returns the trace as CSV with a row for each instruction executed giving the
+    /// instruction, whether its condition passed and all registers afterwards
+    pub fn csv(&self) -> String {
+        let mut out = String::new();
+        let mut values = vec![0; self.registers.len()];
+
+        write!(out, "step,addr,instruction,passed").unwrap();
+
+        for name in &self.registers {
+            write!(out, ",{name}").unwrap();
+        }
+
+        out.push('\n');
+
+        for (step, event) in self.events.iter().enumerate() {
+            if let Some((reg, value)) = event.write {
+                values[reg] = value;
+            }
+
+            write!(
+                out,
+                "{},{},{},{}",
+                step + 1,
+                event.addr,
+                self.instructions[event.addr],
+                event.passed
+            )
+            .unwrap();
+
+            for value in &values {
+                write!(out, ",{value}").unwrap();
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interp::Machine;
+
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        let instructions = "b inc 5 if a > 1
+a inc 1 if b < 5
+c dec -10 if a >= 1
+c inc a if c == 10 or b > 0
+jmp end if c > 100
+end:"
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect::<Vec<Instruction>>();
+
+        let program = Program::new(&instructions).unwrap();
+        let mut machine = Machine::traced(&program);
+
+        machine.run(100).unwrap();
+
+        let trace = machine.trace().unwrap();
+
+        assert_eq!(trace.events().len(), 6);
+        assert_eq!(
+            trace.events()[3],
+            Event {
+                addr: 3,
+                passed: true,
+                write: Some((2, 11))
+            }
+        );
+
+        assert_eq!(
+            trace.csv(),
+            "step,addr,instruction,passed,a,b,c
+1,0,b inc 5 if a > 1,false,0,0,0
+2,1,a inc 1 if b < 5,true,1,0,0
+3,2,c dec -10 if a >= 1,true,1,0,10
+4,3,c inc a if c == 10 or b > 0,true,1,0,11
+5,4,jmp end if c > 100,false,1,0,11
+6,5,end:,true,1,0,11
+"
+        );
+
+        // Untraced machines don't record anything
+        let mut machine = Machine::new(&program);
+
+        machine.run(100).unwrap();
+
+        assert!(machine.trace().is_none());
+    }
+}
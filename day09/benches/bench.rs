@@ -0,0 +1,26 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_line;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, parse};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day09");
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_line(9, input_transform).unwrap())
+    });
+
+    let input = parse_input_line(9, input_transform).unwrap();
+
+    group.bench_function("parse", |b| b.iter(|| parse(&input)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
@@ -9,8 +9,13 @@ fn main() -> Result<(), Box<dyn Error>> {
     let (_, score, garbage) = parse(&input);
 
     // Run parts
-    println!("Part 1: {}", score);
-    println!("Part 2: {}", garbage);
+    if run_part(1) {
+        println!("Part 1: {}", score);
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", garbage);
+    }
 
     Ok(())
 }
@@ -21,7 +26,7 @@ enum State {
     Ignore,
 }
 
-fn parse(input: &[char]) -> (u64, u64, u64) {
+pub fn parse(input: &[char]) -> (u64, u64, u64) {
     let mut state = State::Parse;
     let mut group_count = 0;
     let mut depth = 0;
@@ -62,7 +67,7 @@ fn parse(input: &[char]) -> (u64, u64, u64) {
 
 // Input parsing
 
-fn input_transform(line: String) -> Vec<char> {
+pub fn input_transform(line: String) -> Vec<char> {
     line.chars().collect()
 }
 
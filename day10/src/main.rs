@@ -2,20 +2,25 @@ use std::{collections::VecDeque, error::Error};
 
 use itertools::Itertools;
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_line(10, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input, 255));
-    println!("Part 2: {}", part2(&input, 255));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input, 255));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input, 255));
+    }
 
     Ok(())
 }
 
-fn part1(input: &str, elem_max: u8) -> u64 {
+pub fn part1(input: &str, elem_max: u8) -> u64 {
     let twists = input
         .split(',')
         .map(|e| e.parse::<usize>().expect("Failed to parse number"))
@@ -32,7 +37,7 @@ fn part1(input: &str, elem_max: u8) -> u64 {
     queue[0] as u64 * queue[1] as u64
 }
 
-fn part2(input: &str, elem_max: u8) -> String {
+pub fn part2(input: &str, elem_max: u8) -> String {
     let twists = input
         .as_bytes()
         .iter()
@@ -73,7 +78,7 @@ fn twist(twists: &[usize], queue: &mut VecDeque<u8>, skip_size: &mut usize, rota
 
 // Input parsing
 
-fn input_transform(line: String) -> String {
+pub fn input_transform(line: String) -> String {
     line
 }
 
@@ -1,19 +1,24 @@
 use std::{cmp::max, error::Error};
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_line(11, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[Dir]) -> u64 {
+pub fn part1(input: &[Dir]) -> u64 {
     let mut pos = Pos::new();
 
     for d in input {
@@ -23,7 +28,7 @@ fn part1(input: &[Dir]) -> u64 {
     pos.dist()
 }
 
-fn part2(input: &[Dir]) -> u64 {
+pub fn part2(input: &[Dir]) -> u64 {
     let mut pos = Pos::new();
     let mut max_dist = 0;
 
@@ -84,7 +89,7 @@ impl Pos {
     }
 }
 
-enum Dir {
+pub enum Dir {
     N,
     NE,
     SE,
@@ -105,7 +110,7 @@ enum Dir {
 
 // Input parsing
 
-fn input_transform(line: String) -> Vec<Dir> {
+pub fn input_transform(line: String) -> Vec<Dir> {
     line.split(',')
         .map(|dir| match dir {
             "n" => Dir::N,
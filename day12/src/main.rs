@@ -1,73 +1,64 @@
-use std::{
-    collections::{HashMap, HashSet},
-    error::Error,
-};
+use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::{
+    graph::Graph,
+    input::{parse_input_vec, run_part},
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(12, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input)?);
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[InputEnt]) -> usize {
-    let map = input
-        .iter()
-        .map(|i| (i.program, i.conns.clone()))
-        .collect::<HashMap<_, _>>();
+pub fn part1(input: &[InputEnt]) -> Result<usize, String> {
+    let graph = graph(input);
+    let components = graph.components();
 
-    let mut visited = HashSet::new();
+    let group = components
+        .component(&0)
+        .ok_or_else(|| "Program 0 not found".to_string())?;
 
-    walk(&map, 0, &mut visited)
+    Ok(components.size(group))
 }
 
-fn part2(input: &[InputEnt]) -> usize {
-    let map = input
-        .iter()
-        .map(|i| (i.program, i.conns.clone()))
-        .collect::<HashMap<_, _>>();
-
-    let mut visited = HashSet::new();
-    let mut groups = 0;
-
-    for p in input {
-        if !visited.contains(&p.program) {
-            walk(&map, p.program, &mut visited);
-            groups += 1;
-        }
-    }
-
-    groups
+pub fn part2(input: &[InputEnt]) -> usize {
+    graph(input).components().len()
 }
 
-fn walk(map: &HashMap<u16, Vec<u16>>, node: u16, visited: &mut HashSet<u16>) -> usize {
-    let mut count = 1;
+/// Builds the graph of pipes between programs
+fn graph(input: &[InputEnt]) -> Graph<u16> {
+    let mut graph = Graph::new();
 
-    visited.insert(node);
+    for i in input {
+        graph.add_node(i.program);
 
-    for c in map[&node].iter() {
-        if !visited.contains(c) {
-            count += walk(map, *c, visited);
+        for c in &i.conns {
+            graph.add_edge(i.program, *c);
         }
     }
 
-    count
+    graph
 }
 
 // Input parsing
 
-struct InputEnt {
+pub struct InputEnt {
     program: u16,
     conns: Vec<u16>,
 }
 
-fn input_transform(line: String) -> InputEnt {
+pub fn input_transform(line: String) -> InputEnt {
     let mut iter = line.split_whitespace();
 
     let program = iter
@@ -107,7 +98,7 @@ mod tests {
     #[test]
     fn test1() {
         let input = parse_test_vec(EXAMPLE1, input_transform).unwrap();
-        assert_eq!(part1(&input), 6);
+        assert_eq!(part1(&input), Ok(6));
         assert_eq!(part2(&input), 2);
     }
 }
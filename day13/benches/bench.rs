@@ -0,0 +1,39 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::{input::parse_input_vec, progress::Progress};
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, part1, part2, FireWall};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day13");
+
+    // Long running parts
+    group.sample_size(10);
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(13, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(13, input_transform).unwrap();
+
+    group.bench_function("FireWall::new", |b| b.iter(|| FireWall::new(&input)));
+
+    let firewall = FireWall::new(&input);
+
+    group.bench_function("part1", |b| b.iter(|| part1(&firewall)));
+    group.bench_function("part2", |b| {
+        b.iter(|| {
+            let mut progress = Progress::new("Delay", None).hidden();
+            part2(&firewall, &mut progress).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -1,6 +1,9 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::{
+    input::{parse_input_vec, run_part},
+    progress::{Cancel, Cancelled, Progress},
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
@@ -9,13 +12,22 @@ fn main() -> Result<(), Box<dyn Error>> {
     let firewall = FireWall::new(&input);
 
     // Run parts
-    println!("Part 1: {}", part1(&firewall));
-    println!("Part 2: {}", part2(&firewall));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&firewall));
+    }
+
+    if run_part(2) {
+        let mut progress = Progress::new("Delay", None).cancel_on(Cancel::ctrl_c());
+        let delay = part2(&firewall, &mut progress)?;
+
+        progress.finish();
+        println!("Part 2: {}", delay);
+    }
 
     Ok(())
 }
 
-fn part1(firewall: &FireWall) -> usize {
+pub fn part1(firewall: &FireWall) -> usize {
     let mut score = 0;
 
     for l in 0..firewall.count {
@@ -27,7 +39,7 @@ fn part1(firewall: &FireWall) -> usize {
     score
 }
 
-fn part2(firewall: &FireWall) -> usize {
+pub fn part2(firewall: &FireWall, progress: &mut Progress) -> Result<usize, Cancelled> {
     let mut start_time = 0;
 
     loop {
@@ -45,18 +57,20 @@ fn part2(firewall: &FireWall) -> usize {
         }
 
         start_time += 1;
+
+        progress.set(start_time as u64)?;
     }
 
-    start_time
+    Ok(start_time)
 }
 
-struct FireWall {
+pub struct FireWall {
     layers: Vec<Option<Layer>>,
     count: usize,
 }
 
 impl FireWall {
-    fn new(input: &[InputEnt]) -> Self {
+    pub fn new(input: &[InputEnt]) -> Self {
         let count = input.iter().map(|i| i.depth).max().expect("No layers") + 1;
 
         let mut layers: Vec<Option<Layer>> = (0..count).map(|_| None).collect();
@@ -100,12 +114,12 @@ impl Layer {
 
 // Input parsing
 
-struct InputEnt {
+pub struct InputEnt {
     depth: u16,
     range: u16,
 }
 
-fn input_transform(line: String) -> InputEnt {
+pub fn input_transform(line: String) -> InputEnt {
     let nums = line
         .split_whitespace()
         .map(|s| {
@@ -139,6 +153,7 @@ mod tests {
         let firewall = FireWall::new(&input);
 
         assert_eq!(part1(&firewall), 24);
-        assert_eq!(part2(&firewall), 10);
+        let mut progress = Progress::new("Delay", None).hidden();
+        assert_eq!(part2(&firewall, &mut progress), Ok(10));
     }
 }
@@ -0,0 +1,31 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_line;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{calc_map, input_transform, part1, part2};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day14");
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_line(14, input_transform).unwrap())
+    });
+
+    let input = parse_input_line(14, input_transform).unwrap();
+
+    group.bench_function("calc_map", |b| b.iter(|| calc_map(&input)));
+
+    let map = calc_map(&input);
+
+    group.bench_function("part1", |b| b.iter(|| part1(&map)));
+    group.bench_function("part2", |b| b.iter(|| part2(&map)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -1,6 +1,6 @@
 use std::{collections::VecDeque, error::Error};
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 mod knot_hash;
 
@@ -15,13 +15,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     let map = calc_map(&input);
 
     // Run parts
-    println!("Part 1: {}", part1(&map));
-    println!("Part 2: {}", part2(&map));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&map));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&map));
+    }
 
     Ok(())
 }
 
-fn part1(map: &[Vec<bool>]) -> usize {
+pub fn part1(map: &[Vec<bool>]) -> usize {
     map.iter()
         .map(|row| row.iter().filter(|c| **c).count())
         .sum()
@@ -31,10 +36,10 @@ fn part1(map: &[Vec<bool>]) -> usize {
 enum Region {
     None,
     Unalloc,
-    Region,
+    Alloc,
 }
 
-fn part2(map: &[Vec<bool>]) -> u64 {
+pub fn part2(map: &[Vec<bool>]) -> u64 {
     let mut regions: Vec<Vec<Region>> = map
         .iter()
         .map(|row| {
@@ -58,7 +63,7 @@ fn part2(map: &[Vec<bool>]) -> u64 {
     region_cnt
 }
 
-fn calc_map(input: &str) -> Vec<Vec<bool>> {
+pub fn calc_map(input: &str) -> Vec<Vec<bool>> {
     let mut result = Vec::new();
 
     for i in 0..DIMENSION {
@@ -89,7 +94,7 @@ fn walk_region(map: &mut Vec<Vec<Region>>, x: usize, y: usize) {
     let add_pos =
         |map: &mut Vec<Vec<Region>>, queue: &mut VecDeque<(usize, usize)>, x: usize, y: usize| {
             if map[y][x] == Region::Unalloc {
-                map[y][x] = Region::Region;
+                map[y][x] = Region::Alloc;
                 queue.push_back((x, y));
             }
         };
@@ -114,7 +119,7 @@ fn walk_region(map: &mut Vec<Vec<Region>>, x: usize, y: usize) {
 
 // Input parsing
 
-fn input_transform(line: String) -> String {
+pub fn input_transform(line: String) -> String {
     line
 }
 
@@ -0,0 +1,41 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::{input::parse_input_vec, progress::Progress};
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, part1, part2};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day15");
+
+    // Long running parts
+    group.sample_size(10);
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(15, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(15, input_transform).unwrap();
+
+    group.bench_function("part1", |b| {
+        b.iter(|| {
+            let mut progress = Progress::new("Part 1", None).hidden();
+            part1(&input, &mut progress).unwrap()
+        })
+    });
+
+    group.bench_function("part2", |b| {
+        b.iter(|| {
+            let mut progress = Progress::new("Part 2", None).hidden();
+            part2(&input, &mut progress).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
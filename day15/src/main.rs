@@ -1,33 +1,60 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::{
+    input::{parse_input_vec, run_part},
+    progress::{Cancel, Cancelled, Progress},
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(15, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        let mut progress = Progress::new("Part 1", Some(PART1_PAIRS)).cancel_on(Cancel::ctrl_c());
+        let matches = part1(&input, &mut progress)?;
+
+        progress.finish();
+        println!("Part 1: {}", matches);
+    }
+
+    if run_part(2) {
+        let mut progress = Progress::new("Part 2", Some(PART2_PAIRS)).cancel_on(Cancel::ctrl_c());
+        let matches = part2(&input, &mut progress)?;
+
+        progress.finish();
+        println!("Part 2: {}", matches);
+    }
 
     Ok(())
 }
 
-fn part1(input: &[u64]) -> u64 {
+/// Pairs of values compared in part 1
+pub const PART1_PAIRS: u64 = 40_000_000;
+
+/// Pairs of values compared in part 2
+pub const PART2_PAIRS: u64 = 5_000_000;
+
+pub fn part1(input: &[u64], progress: &mut Progress) -> Result<u64, Cancelled> {
     let mut g1 = Generator::new(input[0], 16807, 0);
     let mut g2 = Generator::new(input[1], 48271, 0);
 
-    count_matches(&mut g1, &mut g2, 40_000_000)
+    count_matches(&mut g1, &mut g2, PART1_PAIRS, progress)
 }
 
-fn part2(input: &[u64]) -> u64 {
+pub fn part2(input: &[u64], progress: &mut Progress) -> Result<u64, Cancelled> {
     let mut g1 = Generator::new(input[0], 16807, 0x03);
     let mut g2 = Generator::new(input[1], 48271, 0x07);
 
-    count_matches(&mut g1, &mut g2, 5_000_000)
+    count_matches(&mut g1, &mut g2, PART2_PAIRS, progress)
 }
 
-fn count_matches(g1: &mut Generator, g2: &mut Generator, iterations: usize) -> u64 {
+fn count_matches(
+    g1: &mut Generator,
+    g2: &mut Generator,
+    iterations: u64,
+    progress: &mut Progress,
+) -> Result<u64, Cancelled> {
     let mut matches = 0;
 
     for _ in 0..iterations {
@@ -37,9 +64,11 @@ fn count_matches(g1: &mut Generator, g2: &mut Generator, iterations: usize) -> u
         if v1 & 0xffff == v2 & 0xffff {
             matches += 1;
         }
+
+        progress.inc(1)?;
     }
 
-    matches
+    Ok(matches)
 }
 
 struct Generator {
@@ -76,7 +105,7 @@ impl Generator {
 
 // Input parsing
 
-fn input_transform(line: String) -> u64 {
+pub fn input_transform(line: String) -> u64 {
     line.split_whitespace()
         .nth(4)
         .expect("Number not found")
@@ -92,7 +121,10 @@ mod tests {
 
     #[test]
     fn test1() {
-        assert_eq!(part1(&EXAMPLE1), 588);
-        assert_eq!(part2(&EXAMPLE1), 309);
+        let mut progress = Progress::new("Part 1", None).hidden();
+        assert_eq!(part1(&EXAMPLE1, &mut progress), Ok(588));
+
+        let mut progress = Progress::new("Part 2", None).hidden();
+        assert_eq!(part2(&EXAMPLE1, &mut progress), Ok(309));
     }
 }
@@ -3,20 +3,25 @@ use std::{
     error::Error,
 };
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_line(16, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input, 16));
-    println!("Part 2: {}", part2(&input, 16));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input, 16));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input, 16));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[Move], prog_cnt: usize) -> String {
+pub fn part1(input: &[Move], prog_cnt: usize) -> String {
     let mut programs = (0..prog_cnt)
         .map(|p| b'a' + p as u8)
         .collect::<VecDeque<_>>();
@@ -44,7 +49,7 @@ fn part1(input: &[Move], prog_cnt: usize) -> String {
 
 const P2ITERS: usize = 1_000_000_000;
 
-fn part2(input: &[Move], prog_cnt: usize) -> String {
+pub fn part2(input: &[Move], prog_cnt: usize) -> String {
     let mut programs = (0..prog_cnt)
         .map(|p| b'a' + p as u8)
         .collect::<VecDeque<_>>();
@@ -93,13 +98,13 @@ fn part2(input: &[Move], prog_cnt: usize) -> String {
 
 // Input parsing
 
-enum Move {
+pub enum Move {
     Spin(usize),
     Exchange(usize, usize),
     Partner(u8, u8),
 }
 
-fn input_transform(line: String) -> Vec<Move> {
+pub fn input_transform(line: String) -> Vec<Move> {
     line.split(',')
         .map(|ins| match ins.chars().next().expect("No start char") {
             's' => Move::Spin(ins[1..].parse::<usize>().expect("Invalid spin")),
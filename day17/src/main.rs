@@ -1,21 +1,26 @@
 use std::{collections::VecDeque, error::Error};
 
-use aoc::input::parse_input_line;
+use aoc::input::{parse_input_line, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_line(17, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(input));
-    println!("Part 2: {}", part2(input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(input));
+    }
 
     Ok(())
 }
 
 const P1ITERS: usize = 2017;
 
-fn part1(input: usize) -> usize {
+pub fn part1(input: usize) -> usize {
     let mut buf = VecDeque::with_capacity(P1ITERS + 1);
 
     buf.push_front(0);
@@ -30,7 +35,7 @@ fn part1(input: usize) -> usize {
 
 const P2ITERS: usize = 50_000_000;
 
-fn part2(input: usize) -> usize {
+pub fn part2(input: usize) -> usize {
     let mut zero_pos: isize = 0;
     let shift = input as isize;
     let mut last = 0;
@@ -48,7 +53,7 @@ fn part2(input: usize) -> usize {
 
 // Input parsing
 
-fn input_transform(line: String) -> usize {
+pub fn input_transform(line: String) -> usize {
     line.parse::<usize>().expect("Invalid number")
 }
 
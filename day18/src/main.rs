@@ -1,228 +1,66 @@
-use std::{
-    collections::{HashMap, VecDeque},
-    error::Error,
-    str::SplitWhitespace,
+use std::error::Error;
+
+use aoc::input::{parse_input_vec, run_part};
+use duet::{
+    compile::{Bytecode, BytecodeVm},
+    instruction::Instruction,
+    io::Sound,
+    scheduler::{Routing, Scheduler, State},
+    vm::{Halt, Stop},
 };
 
-use aoc::input::parse_input_vec;
-
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(18, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
-
-    Ok(())
-}
-
-fn part1(input: &[Instruction]) -> i64 {
-    let mut registers: HashMap<char, i64> = HashMap::new();
-
-    let mut ip = 0;
-    let mut last_freq = 0;
-
-    let get_reg = |reg, registers: &HashMap<char, i64>| *registers.get(&reg).unwrap_or(&0);
-
-    let get_regimm = |regimm: &RegImm, registers: &HashMap<char, i64>| match regimm {
-        RegImm::Imm(v) => *v,
-        RegImm::Reg(reg) => get_reg(*reg, registers),
-    };
-
-    loop {
-        let mut next_ip = ip + 1;
-
-        match &input[ip] {
-            Instruction::Snd(reg) => last_freq = get_reg(*reg, &registers),
-            Instruction::Set(reg, regimm) => {
-                let val = get_regimm(regimm, &registers);
-                *registers.entry(*reg).or_insert(0) = val;
-            }
-            Instruction::Add(reg, regimm) => {
-                let val = get_regimm(regimm, &registers);
-                *registers.entry(*reg).or_insert(0) += val;
-            }
-            Instruction::Mul(reg, regimm) => {
-                let val = get_regimm(regimm, &registers);
-                *registers.entry(*reg).or_insert(0) *= val;
-            }
-            Instruction::Mod(reg, regimm) => {
-                let val = get_regimm(regimm, &registers);
-                *registers.entry(*reg).or_insert(0) %= val;
-            }
-            Instruction::Rcv(reg) => {
-                if get_reg(*reg, &registers) != 0 {
-                    break;
-                }
-            }
-            Instruction::Jgz(regimm1, regimm2) => {
-                let val = get_regimm(regimm1, &registers);
-
-                if val > 0 {
-                    let offset = get_regimm(regimm2, &registers);
-                    next_ip = (ip as isize + offset as isize) as usize;
-                }
-            }
-        }
-
-        ip = next_ip;
-    }
-
-    last_freq
-}
-
-struct Thread {
-    registers: HashMap<char, i64>,
-    ip: usize,
-    inqueue: VecDeque<i64>,
-}
-
-impl Thread {
-    fn new(id: i64) -> Self {
-        let mut registers = HashMap::new();
-
-        registers.insert('p', id);
-
-        Self {
-            registers,
-            ip: 0,
-            inqueue: VecDeque::new(),
-        }
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input)?);
     }
 
-    fn get_reg(&self, reg: char) -> i64 {
-        *self.registers.get(&reg).unwrap_or(&0)
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input)?);
     }
 
-    fn get_regimm(&self, regimm: &RegImm) -> i64 {
-        match regimm {
-            RegImm::Imm(v) => *v,
-            RegImm::Reg(reg) => self.get_reg(*reg),
-        }
-    }
-
-    fn execute(&mut self, input: &[Instruction]) -> Vec<i64> {
-        let mut out = Vec::new();
-
-        loop {
-            let mut next_ip = self.ip + 1;
-
-            match &input[self.ip] {
-                Instruction::Snd(reg) => out.push(self.get_reg(*reg)),
-                Instruction::Set(reg, regimm) => {
-                    let val = self.get_regimm(regimm);
-                    *self.registers.entry(*reg).or_insert(0) = val;
-                }
-                Instruction::Add(reg, regimm) => {
-                    let val = self.get_regimm(regimm);
-                    *self.registers.entry(*reg).or_insert(0) += val;
-                }
-                Instruction::Mul(reg, regimm) => {
-                    let val = self.get_regimm(regimm);
-                    *self.registers.entry(*reg).or_insert(0) *= val;
-                }
-                Instruction::Mod(reg, regimm) => {
-                    let val = self.get_regimm(regimm);
-                    *self.registers.entry(*reg).or_insert(0) %= val;
-                }
-                Instruction::Rcv(reg) => {
-                    if let Some(val) = self.inqueue.pop_front() {
-                        *self.registers.entry(*reg).or_insert(0) = val;
-                    } else {
-                        break;
-                    }
-                }
-                Instruction::Jgz(regimm1, regimm2) => {
-                    let val = self.get_regimm(regimm1);
-
-                    if val > 0 {
-                        let offset = self.get_regimm(regimm2);
-                        next_ip = (self.ip as isize + offset as isize) as usize;
-                    }
-                }
-            }
+    Ok(())
+}
 
-            self.ip = next_ip;
-        }
+pub fn part1(input: &[Instruction]) -> Result<i64, String> {
+    let bytecode = Bytecode::compile(input);
+    let mut vm = BytecodeVm::new(&bytecode);
+    let mut sound = Sound::default();
 
-        out
+    match vm.run(&mut sound) {
+        Stop::Halted(Halt::Received) => Ok(sound.last_freq),
+        Stop::Halted(halt) => Err(format!("No sound was recovered, program {halt}")),
+        Stop::Blocked => unreachable!("Sound never blocks"),
     }
 }
 
-fn part2(input: &[Instruction]) -> usize {
-    let mut t1 = Thread::new(0);
-    let mut t2 = Thread::new(1);
-    let mut t2vals = 0;
+pub fn part2(input: &[Instruction]) -> Result<u64, String> {
+    let bytecode = Bytecode::compile(input);
 
-    loop {
-        let items = t1.execute(input);
-        t2.inqueue.extend(items.iter());
+    // Run two programs sending to each other until both are waiting or have halted
+    let mut scheduler =
+        Scheduler::new(&bytecode, 2, Routing::Pairwise).expect("Two programs can be paired");
 
-        let items = t2.execute(input);
-        t2vals += items.len();
-        t1.inqueue.extend(items.iter());
+    let report = scheduler.run();
 
-        if t1.inqueue.is_empty() {
-            break;
+    for program in &report.programs {
+        if let State::Halted(halt) = program.state {
+            if halt.is_fault() {
+                Err(format!("Program {} stopped with {halt}", program.id))?
+            }
         }
     }
 
-    t2vals
-}
-
-enum Instruction {
-    Snd(Reg),
-    Set(Reg, RegImm),
-    Add(Reg, RegImm),
-    Mul(Reg, RegImm),
-    Mod(Reg, RegImm),
-    Rcv(Reg),
-    Jgz(RegImm, RegImm),
-}
-
-type Reg = char;
-
-enum RegImm {
-    Reg(Reg),
-    Imm(i64),
+    Ok(report.programs[1].sent)
 }
 
 // Input parsing
 
-fn input_transform(line: String) -> Instruction {
-    let mut iter = line.split_whitespace();
-
-    let parse_reg = |iter: &mut SplitWhitespace| {
-        let term = iter.next().expect("Expecting register");
-
-        assert!(term.len() == 1, "Invalid register {term}");
-
-        term.chars().next().unwrap()
-    };
-
-    let parse_regimm = |iter: &mut SplitWhitespace| {
-        let term = iter.next().expect("Expecting register");
-
-        if term.len() == 1 && term.chars().next().unwrap().is_alphabetic() {
-            RegImm::Reg(term.chars().next().unwrap())
-        } else {
-            RegImm::Imm(term.parse::<i64>().expect("Invalid immediate"))
-        }
-    };
-
-    let opcode = iter.next().expect("Opcode not found");
-
-    match opcode {
-        "snd" => Instruction::Snd(parse_reg(&mut iter)),
-        "set" => Instruction::Set(parse_reg(&mut iter), parse_regimm(&mut iter)),
-        "add" => Instruction::Add(parse_reg(&mut iter), parse_regimm(&mut iter)),
-        "mul" => Instruction::Mul(parse_reg(&mut iter), parse_regimm(&mut iter)),
-        "mod" => Instruction::Mod(parse_reg(&mut iter), parse_regimm(&mut iter)),
-        "rcv" => Instruction::Rcv(parse_reg(&mut iter)),
-        "jgz" => Instruction::Jgz(parse_regimm(&mut iter), parse_regimm(&mut iter)),
-        _ => panic!("Invalid opcode {opcode}"),
-    }
+pub fn input_transform(line: String) -> Instruction {
+    line.parse().unwrap_or_else(|e| panic!("{e}"))
 }
 
 #[cfg(test)]
@@ -255,13 +93,28 @@ rcv d
     #[test]
     fn test1() {
         let input = parse_test_vec(EXAMPLE1, input_transform).unwrap();
-        assert_eq!(part1(&input), 4);
-        assert_eq!(part2(&input), 1);
+        assert_eq!(part1(&input), Ok(4));
+        assert_eq!(part2(&input), Ok(1));
     }
 
     #[test]
     fn test2() {
         let input = parse_test_vec(EXAMPLE2, input_transform).unwrap();
-        assert_eq!(part2(&input), 3);
+        assert_eq!(part2(&input), Ok(3));
+    }
+
+    #[test]
+    fn test_faults() {
+        let input = parse_test_vec("snd 1\njgz 1 -2", input_transform).unwrap();
+        assert_eq!(
+            part1(&input),
+            Err("No sound was recovered, program jumped out of range to -1".to_string())
+        );
+
+        let input = parse_test_vec("set a 1\nmod a p\nsnd a", input_transform).unwrap();
+        assert_eq!(
+            part2(&input),
+            Err("Program 0 stopped with division by zero at instruction 1".to_string())
+        );
     }
 }
@@ -0,0 +1,26 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_vec;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, walk};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day19");
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(19, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(19, input_transform).unwrap();
+
+    group.bench_function("walk", |b| b.iter(|| walk(&input, 'Z')));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
@@ -9,13 +9,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     let (part1, part2) = walk(&input, 'Z');
 
     // Run parts
-    println!("Part 1: {}", part1);
-    println!("Part 2: {}", part2);
+    if run_part(1) {
+        println!("Part 1: {}", part1);
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2);
+    }
 
     Ok(())
 }
 
-fn walk(input: &[InputEnt], last: char) -> (String, usize) {
+pub fn walk(input: &[InputEnt], last: char) -> (String, usize) {
     let mut y: isize = 0;
     let mut x: isize = input[0]
         .iter()
@@ -67,9 +72,9 @@ fn walk(input: &[InputEnt], last: char) -> (String, usize) {
 
 // Input parsing
 
-type InputEnt = Vec<char>;
+pub type InputEnt = Vec<char>;
 
-fn input_transform(line: String) -> InputEnt {
+pub fn input_transform(line: String) -> InputEnt {
     line.chars().collect()
 }
 
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, error::Error, ops::AddAssign};
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -10,13 +10,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     let input = parse_input_vec(20, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[Particle]) -> usize {
+pub fn part1(input: &[Particle]) -> usize {
     let (nearest, _) =
         input
             .iter()
@@ -36,7 +41,7 @@ fn part1(input: &[Particle]) -> usize {
     nearest
 }
 
-fn part2(input: &[Particle]) -> usize {
+pub fn part2(input: &[Particle]) -> usize {
     let mut particles = input.to_vec();
 
     loop {
@@ -55,8 +60,8 @@ fn part2(input: &[Particle]) -> usize {
         });
 
         let mut rmlist = positions
-            .into_iter()
-            .flat_map(|(_, elems)| if elems.len() > 1 { elems } else { vec![] })
+            .into_values()
+            .flat_map(|elems| if elems.len() > 1 { elems } else { vec![] })
             .collect::<Vec<_>>();
 
         rmlist.sort();
@@ -74,7 +79,7 @@ fn part2(input: &[Particle]) -> usize {
 }
 
 #[derive(Debug, Clone)]
-struct Particle {
+pub struct Particle {
     position: Triple,
     velocity: Triple,
     accel: Triple,
@@ -105,7 +110,7 @@ impl AddAssign for Triple {
 
 // Input parsing
 
-fn input_transform(line: String) -> Particle {
+pub fn input_transform(line: String) -> Particle {
     lazy_static! {
         static ref RE: Regex = Regex::new(
             r"^p=<(-?\d*),(-?\d*),(-?\d*)>, v=<(-?\d*),(-?\d*),(-?\d*)>, a=<(-?\d*),(-?\d*),(-?\d*)>$"
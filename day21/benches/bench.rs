@@ -0,0 +1,30 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_vec;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, run};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day21");
+
+    // Long running parts
+    group.sample_size(10);
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(21, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(21, input_transform).unwrap();
+
+    group.bench_function("part1", |b| b.iter(|| run(&input, 5)));
+    group.bench_function("part2", |b| b.iter(|| run(&input, 18)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
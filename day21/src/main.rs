@@ -1,19 +1,24 @@
 use std::{collections::HashSet, error::Error};
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(21, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", run(&input, 5));
-    println!("Part 2: {}", run(&input, 18));
+    if run_part(1) {
+        println!("Part 1: {}", run(&input, 5));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", run(&input, 18));
+    }
 
     Ok(())
 }
 
-fn run(input: &[Transform], iterations: usize) -> usize {
+pub fn run(input: &[Transform], iterations: usize) -> usize {
     let mut pic = vec![
         vec![false, true, false],
         vec![false, false, true],
@@ -101,10 +106,10 @@ fn rotate(inarr: PxArray) -> PxArray {
     outarr
 }
 
-type PxArray = Vec<Vec<bool>>;
+pub type PxArray = Vec<Vec<bool>>;
 
 #[derive(Debug)]
-struct Transform {
+pub struct Transform {
     order: usize,
     from: HashSet<PxArray>,
     to: PxArray,
@@ -127,7 +132,7 @@ fn parse_pattern(pattern: &str) -> PxArray {
         .collect()
 }
 
-fn input_transform(line: String) -> Transform {
+pub fn input_transform(line: String) -> Transform {
     let mut terms = line.split(" => ");
 
     let mut from_px = parse_pattern(terms.next().expect("From pattern not found"));
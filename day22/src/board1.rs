@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use crate::{dir::Dir, pos::Pos};
+use super::{dir::Dir, pos::Pos};
 
 pub struct Board1 {
     infected: HashSet<Pos>,
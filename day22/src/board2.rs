@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{dir::Dir, pos::Pos};
+use super::{dir::Dir, pos::Pos};
 
 enum State {
     Weakened,
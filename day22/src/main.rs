@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 mod board1;
 mod board2;
@@ -15,13 +15,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     let input = parse_input_vec(22, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[InputEnt]) -> usize {
+pub fn part1(input: &[InputEnt]) -> usize {
     let mut board = Board1::new(input);
 
     board.steps(10_000);
@@ -29,7 +34,7 @@ fn part1(input: &[InputEnt]) -> usize {
     board.infections()
 }
 
-fn part2(input: &[InputEnt]) -> usize {
+pub fn part2(input: &[InputEnt]) -> usize {
     let mut board = Board2::new(input);
 
     board.steps(10_000_000);
@@ -39,9 +44,9 @@ fn part2(input: &[InputEnt]) -> usize {
 
 // Input parsing
 
-type InputEnt = Vec<bool>;
+pub type InputEnt = Vec<bool>;
 
-fn input_transform(line: String) -> InputEnt {
+pub fn input_transform(line: String) -> InputEnt {
     line.chars()
         .map(|c| match c {
             '#' => true,
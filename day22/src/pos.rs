@@ -1,4 +1,4 @@
-use crate::dir::Dir;
+use super::dir::Dir;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct Pos {
@@ -1,146 +1,313 @@
-use std::{error::Error, str::SplitWhitespace};
+use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
+use duet::{
+    compile::{Bytecode, BytecodeVm},
+    instruction::{parse_program, Instruction, RegImm},
+    vm::{NoIo, Step},
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec(23, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2());
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input)?);
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input)?);
+    }
 
     Ok(())
 }
 
-fn part1(input: &[Instruction]) -> u64 {
-    let mut registers: [i64; 8] = [0; 8];
-
-    let mut ip = 0;
-
+pub fn part1(input: &[Instruction]) -> Result<u64, String> {
+    let bytecode = Bytecode::compile(input);
+    let mut vm = BytecodeVm::new(&bytecode);
     let mut mulins = 0;
 
-    let get_reg = |reg: char, registers: &[i64]| registers[(reg as u8 - b'a') as usize];
-
-    let set_reg =
-        |reg: char, val: i64, registers: &mut [i64]| registers[(reg as u8 - b'a') as usize] = val;
-
-    let get_regimm = |regimm: &RegImm, registers: &[i64]| match regimm {
-        RegImm::Imm(v) => *v,
-        RegImm::Reg(reg) => get_reg(*reg, registers),
-    };
-
-    while ip < input.len() {
-        let mut next_ip = ip + 1;
-
-        match &input[ip] {
-            Instruction::Set(reg, regimm) => {
-                let val = get_regimm(regimm, &registers);
-                set_reg(*reg, val, &mut registers);
-            }
-            Instruction::Sub(reg, regimm) => {
-                let val = get_regimm(regimm, &registers);
-                set_reg(*reg, get_reg(*reg, &registers) - val, &mut registers);
-            }
-            Instruction::Mul(reg, regimm) => {
-                let val = get_regimm(regimm, &registers);
-                set_reg(*reg, get_reg(*reg, &registers) * val, &mut registers);
-                mulins += 1;
-            }
-            Instruction::Jnz(regimm1, regimm2) => {
-                let val = get_regimm(regimm1, &registers);
-
-                if val != 0 {
-                    let offset = get_regimm(regimm2, &registers);
-                    next_ip = (ip as isize + offset as isize) as usize;
+    loop {
+        match vm.step(&mut NoIo) {
+            Step::Executed(ip) => {
+                if let Instruction::Mul(_, _) = input[ip] {
+                    mulins += 1;
                 }
             }
+            Step::Halted(halt) if halt.is_fault() => Err(format!("Program stopped with {halt}"))?,
+            _ => break,
         }
-
-        ip = next_ip;
     }
 
-    mulins
+    Ok(mulins)
 }
 
-fn part2() -> i32 {
-    let mut b = (93 * 100) + 100_000;
-    let c = b + 17_000;
-    let mut d;
-    let mut f;
-    let mut h = 0;
+/// Program shape with the parameters set to zero
+const TEMPLATE: &str = "set b 0
+set c b
+jnz a 2
+jnz 1 5
+mul b 0
+sub b 0
+set c b
+sub c 0
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b 0
+jnz 1 -23
+";
+
+/// Addresses of the parameters in the template
+const SEED: usize = 0;
+const MUL: usize = 4;
+const OFFSET: usize = 5;
+const RANGE: usize = 7;
+const STEP: usize = 30;
+
+/// Values of b checked for composites by the program when a is 1
+#[derive(Debug, PartialEq, Eq)]
+pub struct Params {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+}
 
-    loop {
-        f = 1;
-        d = 2;
+impl Params {
+    /// Extracts the parameters from the program, checking it matches the expected shape
+    pub fn new(program: &[Instruction]) -> Result<Self, String> {
+        let template = parse_program(TEMPLATE).expect("Template is invalid");
+
+        if program.len() != template.len() {
+            Err(format!(
+                "Program has {} instructions, expecting {}",
+                program.len(),
+                template.len()
+            ))?
+        }
 
-        while d < b {
-            if b % d == 0 {
-                f = 0;
-                break;
+        let mut params = [0; 5];
+        let mut next_param = 0;
+
+        for (addr, (got, expected)) in program.iter().zip(&template).enumerate() {
+            if [SEED, MUL, OFFSET, RANGE, STEP].contains(&addr) {
+                match split_imm(got) {
+                    Some((shape, value)) if shape == *expected => {
+                        params[next_param] = value;
+                        next_param += 1;
+                    }
+                    _ => Err(format!(
+                        "Unexpected instruction '{got}' at {addr}, expecting '{}' with an immediate value",
+                        expected.mnemonic()
+                    ))?,
+                }
+            } else if got != expected {
+                Err(format!(
+                    "Unexpected instruction '{got}' at {addr}, expecting '{expected}'"
+                ))?
             }
-
-            d += 1;
         }
 
-        if f == 0 {
-            h += 1;
+        let [seed, mul, offset, range, step] = params;
+
+        // b = seed * mul - offset, c = b - range, b -= step until b == c
+        let start = seed
+            .checked_mul(mul)
+            .and_then(|b| b.checked_sub(offset))
+            .ok_or("Start value overflows")?;
+        let end = start.checked_sub(range).ok_or("End value overflows")?;
+        let step = step.checked_neg().ok_or("Step overflows")?;
+
+        // The inner loops count up from 2 until they reach b
+        if start < 3 {
+            Err(format!(
+                "Program never terminates: b starts at {start}, which is less than 3"
+            ))?
         }
 
-        if b - c == 0 {
-            break;
+        // Make sure the outer loop terminates
+        let span = end.checked_sub(start).ok_or("Range overflows")?;
+
+        if step <= 0 || span < 0 || span % step != 0 {
+            Err(format!(
+                "Program never terminates: b runs from {start} to {end} in steps of {step}"
+            ))?
         }
 
-        b += 17;
+        Ok(Self { start, end, step })
     }
-
-    h
 }
 
-enum Instruction {
-    Set(Reg, RegImm),
-    Sub(Reg, RegImm),
-    Mul(Reg, RegImm),
-    Jnz(RegImm, RegImm),
+/// Splits an instruction with an immediate second operand into the instruction with
+/// the immediate set to zero and the immediate value
+fn split_imm(instruction: &Instruction) -> Option<(Instruction, i64)> {
+    match *instruction {
+        Instruction::Set(x, RegImm::Imm(v)) => Some((Instruction::Set(x, RegImm::Imm(0)), v)),
+        Instruction::Mul(x, RegImm::Imm(v)) => Some((Instruction::Mul(x, RegImm::Imm(0)), v)),
+        Instruction::Sub(x, RegImm::Imm(v)) => Some((Instruction::Sub(x, RegImm::Imm(0)), v)),
+        _ => None,
+    }
 }
 
-type Reg = char;
+pub fn part2(input: &[Instruction]) -> Result<u64, String> {
+    let params = Params::new(input)?;
+
+    // Count the composite values of b
+    let composites = (params.start..=params.end)
+        .step_by(params.step as usize)
+        .filter(|&b| is_composite(b))
+        .count();
+
+    Ok(composites as u64)
+}
 
-enum RegImm {
-    Reg(Reg),
-    Imm(i64),
+/// Returns true if the number has a divisor d where 2 <= d < n
+fn is_composite(n: i64) -> bool {
+    n > 3 && (2..).take_while(|&d| d <= n / d).any(|d| n % d == 0)
 }
 
 // Input parsing
 
-fn input_transform(line: String) -> Instruction {
-    let mut iter = line.split_whitespace();
+pub fn input_transform(line: String) -> Instruction {
+    line.parse().unwrap_or_else(|e| panic!("{e}"))
+}
 
-    let parse_reg = |iter: &mut SplitWhitespace| {
-        let term = iter.next().expect("Expecting register");
+#[cfg(test)]
+mod tests {
+    use duet::{
+        instruction::Reg,
+        optimise::{optimise, OptimisedVm},
+        vm::{Halt, Stop},
+    };
 
-        assert!(term.len() == 1, "Invalid register {term}");
+    use super::*;
+
+    #[test]
+    fn test_params() {
+        let mut program = parse_program(TEMPLATE).unwrap();
+
+        program[SEED] = "set b 57".parse().unwrap();
+        program[MUL] = "mul b 100".parse().unwrap();
+        program[OFFSET] = "sub b -100000".parse().unwrap();
+        program[RANGE] = "sub c -17000".parse().unwrap();
+        program[STEP] = "sub b -17".parse().unwrap();
+
+        assert_eq!(
+            Params::new(&program),
+            Ok(Params {
+                start: 105_700,
+                end: 122_700,
+                step: 17
+            })
+        );
+
+        // Small range
+        program[SEED] = "set b 1".parse().unwrap();
+        program[MUL] = "mul b 1".parse().unwrap();
+        program[OFFSET] = "sub b -2".parse().unwrap();
+        program[RANGE] = "sub c -34".parse().unwrap();
+
+        // 3, 20 and 37
+        assert_eq!(part2(&program), Ok(1));
+
+        // The inner loops never reach b if it is less than 3
+        program[OFFSET] = "sub b -1".parse().unwrap();
+
+        assert_eq!(
+            Params::new(&program),
+            Err("Program never terminates: b starts at 2, which is less than 3".to_string())
+        );
+    }
 
-        term.chars().next().unwrap()
-    };
+    #[test]
+    fn test_bad_program() {
+        let mut program = parse_program(TEMPLATE).unwrap();
+
+        program[STEP] = "sub b -17".parse().unwrap();
+        program[RANGE] = "sub c -10".parse().unwrap();
+        assert!(Params::new(&program)
+            .unwrap_err()
+            .contains("never terminates"));
+
+        // The end of the range overflows
+        program[SEED] = format!("set b {}", i64::MAX).parse().unwrap();
+        program[MUL] = "mul b 1".parse().unwrap();
+        program[RANGE] = format!("sub c {}", i64::MIN + 1).parse().unwrap();
+        program[STEP] = "sub b -1".parse().unwrap();
+        assert_eq!(
+            Params::new(&program),
+            Err("End value overflows".to_string())
+        );
+
+        program[SEED] = "set b 0".parse().unwrap();
+        program[MUL] = "mul b 0".parse().unwrap();
+        program[RANGE] = "sub c 0".parse().unwrap();
+        program[12] = "mul g d".parse().unwrap();
+        assert_eq!(
+            Params::new(&program),
+            Err("Unexpected instruction 'mul g d' at 12, expecting 'mul g e'".to_string())
+        );
+
+        program[12] = "mul g e".parse().unwrap();
+        program[STEP] = "sub b a".parse().unwrap();
+        assert_eq!(
+            Params::new(&program),
+            Err(
+                "Unexpected instruction 'sub b a' at 30, expecting 'sub' with an immediate value"
+                    .to_string()
+            )
+        );
+
+        assert!(Params::new(&program[1..]).is_err());
+
+        // Faults are reported rather than panicking
+        let program = parse_program("set a 3\nmul a a\njnz 1 -1").unwrap();
+        assert_eq!(
+            part1(&program),
+            Err("Program stopped with overflow at instruction 1".to_string())
+        );
+    }
 
-    let parse_regimm = |iter: &mut SplitWhitespace| {
-        let term = iter.next().expect("Expecting register");
+    #[test]
+    fn test_optimised_run() {
+        let mut program = parse_program(TEMPLATE).unwrap();
 
-        if term.len() == 1 && term.chars().next().unwrap().is_alphabetic() {
-            RegImm::Reg(term.chars().next().unwrap())
-        } else {
-            RegImm::Imm(term.parse::<i64>().expect("Invalid immediate"))
-        }
-    };
+        program[SEED] = "set b 57".parse().unwrap();
+        program[MUL] = "mul b 100".parse().unwrap();
+        program[OFFSET] = "sub b -100000".parse().unwrap();
+        program[RANGE] = "sub c -1700".parse().unwrap();
+        program[STEP] = "sub b -17".parse().unwrap();
+
+        // The optimised program runs to completion with a = 1
+        let optimised = optimise(&program);
+        let mut vm = OptimisedVm::new(&optimised);
 
-    let opcode = iter.next().expect("Opcode not found");
+        vm.set_reg(Reg::new('a').unwrap(), 1);
 
-    match opcode {
-        "set" => Instruction::Set(parse_reg(&mut iter), parse_regimm(&mut iter)),
-        "sub" => Instruction::Sub(parse_reg(&mut iter), parse_regimm(&mut iter)),
-        "mul" => Instruction::Mul(parse_reg(&mut iter), parse_regimm(&mut iter)),
-        "jnz" => Instruction::Jnz(parse_regimm(&mut iter), parse_regimm(&mut iter)),
-        _ => panic!("Invalid opcode {opcode}"),
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::Finished));
+        assert_eq!(
+            vm.reg(Reg::new('h').unwrap()) as u64,
+            part2(&program).unwrap()
+        );
     }
 }
@@ -0,0 +1,36 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::{input::parse_input_vec, progress::Progress};
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, walk, State, WalkResult};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day24");
+
+    // Long running parts
+    group.sample_size(10);
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(24, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(24, input_transform).unwrap();
+
+    group.bench_function("walk", |b| {
+        b.iter(|| {
+            let mut result = WalkResult::default();
+            let mut progress = Progress::new("Bridges", None).hidden();
+            walk(State::new(input.to_vec()), &mut result, &mut progress).unwrap();
+            result
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
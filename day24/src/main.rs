@@ -1,6 +1,9 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::{
+    input::{parse_input_vec, run_part},
+    progress::{Cancel, Cancelled, Progress},
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
@@ -8,31 +11,38 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // Walk the connectors
     let mut result = WalkResult::default();
+    let mut progress = Progress::new("Bridges", None).cancel_on(Cancel::ctrl_c());
 
-    walk(State::new(input.to_vec()), &mut result);
+    walk(State::new(input.to_vec()), &mut result, &mut progress)?;
+    progress.finish();
 
     // Report results
-    println!("Part 1: {}", result.strongest);
-    println!("Part 2: {}", result.longest_strength);
+    if run_part(1) {
+        println!("Part 1: {}", result.strongest);
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", result.longest_strength);
+    }
 
     Ok(())
 }
 
 #[derive(Clone)]
-struct Conn {
+pub struct Conn {
     port_a: u8,
     port_b: u8,
 }
 
 #[derive(Default)]
-struct WalkResult {
+pub struct WalkResult {
     strongest: u64,
     longest: usize,
     longest_strength: u64,
 }
 
 #[derive(Clone)]
-struct State {
+pub struct State {
     available: Vec<Conn>,
     last: u8,
     length: usize,
@@ -40,7 +50,7 @@ struct State {
 }
 
 impl State {
-    fn new(available: Vec<Conn>) -> Self {
+    pub fn new(available: Vec<Conn>) -> Self {
         Self {
             available,
             last: 0,
@@ -50,7 +60,13 @@ impl State {
     }
 }
 
-fn walk(state: State, result: &mut WalkResult) {
+pub fn walk(
+    state: State,
+    result: &mut WalkResult,
+    progress: &mut Progress,
+) -> Result<(), Cancelled> {
+    progress.inc(1)?;
+
     // Find connectors
     let suitable = state
         .available
@@ -87,14 +103,16 @@ fn walk(state: State, result: &mut WalkResult) {
             next_state.length += 1;
             next_state.strength += next.port_a as u64 + next.port_b as u64;
 
-            walk(next_state, result);
+            walk(next_state, result, progress)?;
         }
     }
+
+    Ok(())
 }
 
 // Input parsing
 
-fn input_transform(line: String) -> Conn {
+pub fn input_transform(line: String) -> Conn {
     let nums = line
         .split('/')
         .map(|p| p.parse::<u8>().expect("Invalid number"))
@@ -127,8 +145,9 @@ mod tests {
         let input = parse_test_vec(EXAMPLE1, input_transform).unwrap();
 
         let mut result = WalkResult::default();
+        let mut progress = Progress::new("Bridges", None).hidden();
 
-        walk(State::new(input.to_vec()), &mut result);
+        walk(State::new(input.to_vec()), &mut result, &mut progress).unwrap();
 
         assert_eq!(result.strongest, 31);
         assert_eq!(result.longest_strength, 19);
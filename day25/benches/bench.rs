@@ -0,0 +1,33 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_vec;
+
+#[path = "../src/main.rs"]
+#[allow(dead_code, unused_imports)]
+mod day;
+
+use day::{input_transform, parse_program, part1};
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day25");
+
+    // Long running parts
+    group.sample_size(10);
+
+    group.bench_function("input_transform", |b| {
+        b.iter(|| parse_input_vec(25, input_transform).unwrap())
+    });
+
+    let input = parse_input_vec(25, input_transform).unwrap();
+
+    group.bench_function("parse_program", |b| b.iter(|| parse_program(&input)));
+
+    let program = parse_program(&input);
+
+    group.bench_function("part1", |b| b.iter(|| part1(&program)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
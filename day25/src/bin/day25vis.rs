@@ -0,0 +1,87 @@
+use std::{env, error::Error, fs};
+
+use aoc::{gif::Gif, input::parse_input_vec};
+use day25::{
+    blueprint::{parse_program, Program},
+    notation,
+    turing::Simulator,
+    vis::{palette, record, render},
+};
+
+/// Number of snapshots of the tape
+const ROWS: u64 = 600;
+
+/// Rows added in each frame of the animation
+const ROWS_PER_FRAME: usize = 10;
+
+const MAX_WIDTH: usize = 1000;
+
+/// Steps to run a machine given in compact notation for
+const TABLE_STEPS: u64 = 100_000;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Use a machine in compact notation if one is given, otherwise the puzzle input
+    let program = match env::args().nth(1) {
+        Some(table) => {
+            let machine = notation::parse(&table)?;
+
+            // Only show up to when the machine halts
+            let diag_steps = Simulator::new(&machine, 0)?.run(TABLE_STEPS);
+
+            Program {
+                start: 0,
+                diag_steps,
+                machine,
+            }
+        }
+        None => parse_program(&parse_input_vec(25, |line| line)?),
+    };
+
+    let machine = &program.machine;
+    let interval = program.diag_steps.div_ceil(ROWS).max(1);
+
+    let rows = record(machine, program.start, program.diag_steps, interval)?;
+    let diagram = render(&rows, machine.symbols(), MAX_WIDTH);
+
+    println!(
+        "{} rows of {} steps, {} cells per pixel",
+        rows.len(),
+        interval,
+        diagram.cells_per_pixel
+    );
+
+    // Scale small diagrams up
+    let width = diagram.width as u16;
+    let height = diagram.pixels.len() as u16;
+    let x_scale = (MAX_WIDTH as u16 / width).clamp(1, 8);
+    let y_scale = (ROWS as u16 / height).clamp(1, 8);
+
+    fs::create_dir_all("vis")?;
+
+    let file = "vis/day25.gif";
+    let mut gif = Gif::new(
+        file,
+        &palette(machine.symbols()),
+        width,
+        height,
+        x_scale,
+        y_scale,
+    )?;
+
+    // Draw the diagram a few rows at a time
+    let mut frame = vec![vec![0; diagram.width]; diagram.pixels.len()];
+
+    for (i, row) in diagram.pixels.iter().enumerate() {
+        frame[i].clone_from(row);
+
+        if (i + 1) % ROWS_PER_FRAME == 0 || i + 1 == diagram.pixels.len() {
+            gif.draw_frame(frame.clone(), 2)?;
+        }
+    }
+
+    gif.delay(500)?;
+
+    println!("Written {file}");
+
+    Ok(())
+}
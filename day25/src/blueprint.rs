@@ -0,0 +1,312 @@
+use std::{fmt::Write, slice::Iter};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::turing::{Action, Machine, Move, Next, Symbol};
+
+/// Turing machine blueprint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    /// Index of the state to begin in
+    pub start: usize,
+    /// Number of steps to run before taking the checksum
+    pub diag_steps: u64,
+    pub machine: Machine,
+}
+
+/// Parses a blueprint
+pub fn parse_program(input: &[String]) -> Program {
+    lazy_static! {
+        static ref RE1: Regex = Regex::new(r"^Begin in state (\w+).$").unwrap();
+        static ref RE2: Regex =
+            Regex::new(r"^Perform a diagnostic checksum after (\d*) steps.$").unwrap();
+        static ref RE3: Regex = Regex::new(r"^In state (\w+):$").unwrap();
+    }
+
+    let mut iter = input.iter().peekable();
+
+    // Parse first line
+    let init_state = get_string(&RE1, &mut iter).to_string();
+
+    // Parse second line
+    let diag_steps = get_int(&RE2, &mut iter);
+
+    let mut names = Vec::new();
+    let mut states = Vec::new();
+
+    while let Some(line) = iter.next() {
+        assert!(line.is_empty());
+
+        names.push(get_string(&RE3, &mut iter).to_string());
+
+        // One action for each symbol until a blank line
+        let mut actions = Vec::new();
+
+        while iter.peek().is_some_and(|line| !line.is_empty()) {
+            actions.push(parse_action(&mut iter, actions.len()));
+        }
+
+        states.push(actions);
+    }
+
+    let symbols = states.first().map(|s| s.len()).unwrap_or(2);
+
+    // Resolve state names
+    let state = |name: &str| {
+        names
+            .iter()
+            .position(|n| n == name)
+            .unwrap_or_else(|| panic!("State {name} does not exist"))
+    };
+
+    let actions = states
+        .iter()
+        .map(|actions| {
+            actions
+                .iter()
+                .map(|(write, mv, next)| Action {
+                    write: vec![*write],
+                    moves: vec![*mv],
+                    next: match next {
+                        Some(name) => Next::State(state(name)),
+                        None => Next::Halt,
+                    },
+                })
+                .collect()
+        })
+        .collect();
+
+    let start = state(&init_state);
+    let machine = Machine::new(names, symbols, 1, actions).unwrap_or_else(|e| panic!("{e}"));
+
+    Program {
+        start,
+        diag_steps,
+        machine,
+    }
+}
+
+type Lines<'a> = std::iter::Peekable<Iter<'a, String>>;
+
+/// Parses the action for a symbol, returning the symbol written, the move and the
+/// next state name, or None to halt
+fn parse_action(iter: &mut Lines, value: usize) -> (Symbol, Move, Option<String>) {
+    lazy_static! {
+        static ref RE1: Regex = Regex::new(r"^    - Write the value (\d+).$").unwrap();
+        static ref RE2: Regex = Regex::new(r"^    - Move one slot to the ([^\.]*).$").unwrap();
+        static ref RE3: Regex = Regex::new(r"^    - Continue with state (\w+).$").unwrap();
+    }
+
+    let line = iter.next().expect("Line missing");
+
+    let expected = format!("  If the current value is {value}:");
+    assert_eq!(line, &expected);
+
+    let write = Symbol::try_from(get_int(&RE1, iter)).expect("Invalid value");
+
+    let line = iter.next().expect("Lines exhausted");
+
+    let mv = if line == "    - Stay in the same slot." {
+        Move::Stay
+    } else {
+        match get_string_from_str(&RE2, line) {
+            "right" => Move::Right,
+            "left" => Move::Left,
+            _ => panic!("Invalid direction"),
+        }
+    };
+
+    let line = iter.next().expect("Lines exhausted");
+
+    let next = if line == "    - Halt." {
+        None
+    } else {
+        Some(get_string_from_str(&RE3, line).to_string())
+    };
+
+    (write, mv, next)
+}
+
+fn get_int(re: &Regex, iter: &mut Lines) -> u64 {
+    let line = iter.next().expect("Lines exhausted");
+
+    get_int_from_str(re, line)
+}
+
+fn get_int_from_str(re: &Regex, line: &str) -> u64 {
+    get_string_from_str(re, line)
+        .parse::<u64>()
+        .expect("Invalid number")
+}
+
+fn get_string<'a>(re: &Regex, iter: &mut Lines<'a>) -> &'a str {
+    let line = iter.next().expect("Lines exhausted");
+
+    get_string_from_str(re, line)
+}
+
+fn get_string_from_str<'a>(re: &Regex, line: &'a str) -> &'a str {
+    re.captures(line)
+        .unwrap_or_else(|| panic!("Invalid input line: {line}"))
+        .iter()
+        .nth(1)
+        .expect("No match")
+        .map(|m| m.as_str())
+        .expect("Map failed")
+}
+
+/// Writes a program as a blueprint which parse_program reads back
+pub fn write_program(program: &Program) -> Result<String, String> {
+    let machine = &program.machine;
+
+    if machine.tapes() != 1 {
+        Err("Blueprints can only describe machines with one tape")?
+    }
+
+    if let Some(name) = machine
+        .names()
+        .iter()
+        .find(|name| name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+    {
+        Err(format!("State name '{name}' can't be used in a blueprint"))?
+    }
+
+    let start = machine
+        .names()
+        .get(program.start)
+        .ok_or_else(|| format!("Start state {} doesn't exist", program.start))?;
+
+    let mut out = String::new();
+
+    writeln!(out, "Begin in state {start}.").unwrap();
+    writeln!(
+        out,
+        "Perform a diagnostic checksum after {} steps.",
+        program.diag_steps
+    )
+    .unwrap();
+
+    for (state, name) in machine.names().iter().enumerate() {
+        writeln!(out).unwrap();
+        writeln!(out, "In state {name}:").unwrap();
+
+        for value in 0..machine.symbols() {
+            let action = machine.action(state, &[value as Symbol]);
+
+            writeln!(out, "  If the current value is {value}:").unwrap();
+            writeln!(out, "    - Write the value {}.", action.write[0]).unwrap();
+
+            match action.moves[0] {
+                Move::Left => writeln!(out, "    - Move one slot to the left.").unwrap(),
+                Move::Right => writeln!(out, "    - Move one slot to the right.").unwrap(),
+                Move::Stay => writeln!(out, "    - Stay in the same slot.").unwrap(),
+            }
+
+            match action.next {
+                Next::State(next) => {
+                    writeln!(out, "    - Continue with state {}.", machine.names()[next]).unwrap()
+                }
+                Next::Halt => writeln!(out, "    - Halt.").unwrap(),
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notation;
+
+    use super::*;
+
+    const EXAMPLE: &str = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.
+";
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let program = parse_program(&lines(EXAMPLE));
+
+        assert_eq!(write_program(&program).unwrap(), EXAMPLE);
+
+        // Same machine in compact form
+        assert_eq!(
+            notation::format(&program.machine),
+            Ok("1RB0LB_1LA1RA".to_string())
+        );
+
+        // Halting, staying put, more symbols and a start other than the first state
+        let machine = notation::parse("2RB1NH1RA_1LA0LA0NH").unwrap();
+
+        let program = Program {
+            start: 1,
+            diag_steps: 100,
+            machine,
+        };
+
+        let blueprint = write_program(&program).unwrap();
+
+        assert!(blueprint.starts_with("Begin in state B.\n"));
+        assert!(blueprint.contains("    - Stay in the same slot.\n    - Halt.\n"));
+        assert_eq!(parse_program(&lines(&blueprint)), program);
+    }
+
+    #[test]
+    fn test_unwritable() {
+        let machine = notation::parse("1RB1LB_1LA1RH").unwrap();
+
+        let mut program = Program {
+            start: 0,
+            diag_steps: 1,
+            machine,
+        };
+
+        assert!(write_program(&program).is_ok());
+
+        // Start state out of range
+        program.start = 2;
+
+        assert_eq!(
+            write_program(&program),
+            Err("Start state 2 doesn't exist".to_string())
+        );
+
+        program.start = 0;
+
+        let halt = Action {
+            write: vec![0, 0],
+            moves: vec![Move::Stay, Move::Stay],
+            next: Next::Halt,
+        };
+
+        program.machine = Machine::new(vec!["A".to_string()], 2, 2, vec![vec![halt; 4]]).unwrap();
+
+        assert!(write_program(&program).is_err());
+    }
+}
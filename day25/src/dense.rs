@@ -0,0 +1,383 @@
+use crate::turing::{Machine, Next};
+
+/// Cells in a tape block
+pub const BLOCK: i64 = 8;
+
+/// Bit tape stored densely in blocks of eight cells, which grows in both directions
+#[derive(Debug, Clone, Default)]
+pub struct DenseTape {
+    blocks: Vec<u8>,
+    /// Block number of the first block
+    first: i64,
+}
+
+impl DenseTape {
+    /// Returns the value of a cell
+    pub fn get(&self, pos: i64) -> bool {
+        self.block(pos.div_euclid(BLOCK)) & (1 << pos.rem_euclid(BLOCK)) != 0
+    }
+
+    /// Sets the value of a cell
+    pub fn set(&mut self, pos: i64, value: bool) {
+        let block = self.block_mut(pos.div_euclid(BLOCK));
+        let bit = 1 << pos.rem_euclid(BLOCK);
+
+        if value {
+            *block |= bit;
+        } else {
+            *block &= !bit;
+        }
+    }
+
+    /// Returns the cells of a block, with the lowest bit for the leftmost cell
+    pub fn block(&self, block: i64) -> u8 {
+        usize::try_from(block - self.first)
+            .ok()
+            .and_then(|i| self.blocks.get(i))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Sets the cells of a block
+    pub fn set_block(&mut self, block: i64, cells: u8) {
+        *self.block_mut(block) = cells;
+    }
+
+    /// Returns the number of cells set
+    pub fn count(&self) -> usize {
+        self.blocks.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    /// Returns the range of cell positions which have been stored
+    pub fn range(&self) -> (i64, i64) {
+        (
+            self.first * BLOCK,
+            (self.first + self.blocks.len() as i64) * BLOCK,
+        )
+    }
+
+    fn block_mut(&mut self, block: i64) -> &mut u8 {
+        if self.blocks.is_empty() {
+            self.first = block;
+        }
+
+        if block < self.first {
+            // Grow to the left by at least double to keep prepending cheap
+            let grow = (self.first - block).max(self.blocks.len() as i64) as usize;
+
+            self.blocks.splice(0..0, std::iter::repeat_n(0, grow));
+            self.first -= grow as i64;
+        }
+
+        let i = (block - self.first) as usize;
+
+        if i >= self.blocks.len() {
+            self.blocks.resize(i + 1, 0);
+        }
+
+        &mut self.blocks[i]
+    }
+}
+
+/// Single step transition for a binary machine
+#[derive(Debug, Clone, Copy)]
+struct Rule {
+    write: bool,
+    offset: i64,
+    next: Next,
+}
+
+/// Effect of running a state from a position in a block until the head leaves it
+#[derive(Debug, Clone, Copy)]
+struct Macro {
+    cells: u8,
+    state: Next,
+    /// Head position relative to the start of the block
+    head: i64,
+    steps: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Entry {
+    Unknown,
+    /// The machine never leaves the block
+    Loop,
+    Macro(Macro),
+}
+
+/// Runs a binary single tape machine on a dense tape, moving over whole blocks at a
+/// time where possible
+pub struct MacroSimulator {
+    rules: Vec<[Rule; 2]>,
+    state: Next,
+    head: i64,
+    tape: DenseTape,
+    steps: u64,
+    cache: Vec<Entry>,
+}
+
+impl MacroSimulator {
+    /// Creates a simulator with a blank tape in the given state
+    pub fn new(machine: &Machine, start: usize) -> Result<Self, String> {
+        if machine.symbols() != 2 || machine.tapes() != 1 {
+            Err("Only machines with two symbols and one tape can be run on a dense tape")?
+        }
+
+        machine.check_start(start)?;
+
+        let rules = (0..machine.states())
+            .map(|state| {
+                [0, 1].map(|read| {
+                    let action = machine.action(state, &[read]);
+
+                    Rule {
+                        write: action.write[0] == 1,
+                        offset: action.moves[0].offset(),
+                        next: action.next,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let cache = vec![Entry::Unknown; rules.len() * 256 * BLOCK as usize];
+
+        Ok(Self {
+            rules,
+            state: Next::State(start),
+            head: 0,
+            tape: DenseTape::default(),
+            steps: 0,
+            cache,
+        })
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> Next {
+        self.state
+    }
+
+    /// Returns true if the machine has halted
+    pub fn halted(&self) -> bool {
+        self.state == Next::Halt
+    }
+
+    /// Returns the head position
+    pub fn head(&self) -> i64 {
+        self.head
+    }
+
+    /// Returns the tape
+    pub fn tape(&self) -> &DenseTape {
+        &self.tape
+    }
+
+    /// Returns the number of steps executed
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Executes a single step, returning false if the machine has halted
+    pub fn step(&mut self) -> bool {
+        let Next::State(state) = self.state else {
+            return false;
+        };
+
+        let rule = self.rules[state][self.tape.get(self.head) as usize];
+
+        self.tape.set(self.head, rule.write);
+        self.head += rule.offset;
+        self.state = rule.next;
+        self.steps += 1;
+
+        true
+    }
+
+    /// Runs for a number of steps or until the machine halts, returning the number
+    /// of steps executed
+    pub fn run(&mut self, steps: u64) -> u64 {
+        let start = self.steps;
+        let end = start.saturating_add(steps);
+
+        while self.steps < end {
+            let Next::State(state) = self.state else {
+                break;
+            };
+
+            let block = self.head.div_euclid(BLOCK);
+            let pos = self.head.rem_euclid(BLOCK);
+            let cells = self.tape.block(block);
+
+            match self.lookup(state, cells, pos) {
+                Some(m) if m.steps <= end - self.steps => {
+                    self.tape.set_block(block, m.cells);
+                    self.head = block * BLOCK + m.head;
+                    self.state = m.state;
+                    self.steps += m.steps;
+                }
+                _ => {
+                    self.step();
+                }
+            }
+        }
+
+        self.steps - start
+    }
+
+    /// Returns the macro step for a state starting at a position in a block
+    fn lookup(&mut self, state: usize, cells: u8, pos: i64) -> Option<Macro> {
+        let index = (state * 256 + cells as usize) * BLOCK as usize + pos as usize;
+
+        if let Entry::Unknown = self.cache[index] {
+            self.cache[index] = self.compute(state, cells, pos);
+        }
+
+        match self.cache[index] {
+            Entry::Macro(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    fn compute(&self, state: usize, mut cells: u8, mut head: i64) -> Entry {
+        // Every configuration inside the block has been visited by this point if the
+        // head hasn't left
+        let limit = (self.rules.len() as u64) * 256 * BLOCK as u64;
+
+        let mut state = Next::State(state);
+        let mut steps = 0;
+
+        while let Next::State(s) = state {
+            if !(0..BLOCK).contains(&head) {
+                break;
+            }
+
+            if steps > limit {
+                return Entry::Loop;
+            }
+
+            let bit = 1 << head;
+            let rule = self.rules[s][(cells & bit != 0) as usize];
+
+            if rule.write {
+                cells |= bit;
+            } else {
+                cells &= !bit;
+            }
+
+            head += rule.offset;
+            state = rule.next;
+            steps += 1;
+        }
+
+        Entry::Macro(Macro {
+            cells,
+            state,
+            head,
+            steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        notation::parse,
+        turing::{Action, Move, Simulator},
+    };
+
+    use super::*;
+
+    /// Checks the macro simulator matches the simple one after a number of steps
+    fn compare(machine: &Machine, steps: u64) {
+        let mut sim = Simulator::new(machine, 0).unwrap();
+        let mut fast = MacroSimulator::new(machine, 0).unwrap();
+
+        assert_eq!(fast.run(steps), sim.run(steps));
+        assert_eq!(fast.state(), sim.state());
+        assert_eq!(fast.head(), sim.tape(0).head());
+        assert_eq!(fast.tape().count(), sim.tape(0).count(1));
+
+        let (from, to) = fast.tape().range();
+
+        for pos in from..to {
+            assert_eq!(fast.tape().get(pos), sim.tape(0).get(pos) == 1);
+        }
+    }
+
+    #[test]
+    fn test_tape() {
+        let mut tape = DenseTape::default();
+
+        tape.set(3, true);
+        tape.set(-20, true);
+        tape.set(100, true);
+        tape.set(3, false);
+        tape.set(-1, true);
+
+        assert_eq!(tape.count(), 3);
+        assert!(tape.get(-20) && tape.get(-1) && tape.get(100));
+        assert!(!tape.get(3) && !tape.get(-1000) && !tape.get(1000));
+        assert_eq!(tape.block(-1), 0x80);
+
+        let (from, to) = tape.range();
+        assert!(from <= -20 && to > 100);
+    }
+
+    #[test]
+    fn test_busy_beaver() {
+        // Four state champion halts after 107 steps with 13 ones
+        let bb4 = parse("1RB1LB_1LA0LC_1RH1LD_1RD0RA").unwrap();
+
+        let mut fast = MacroSimulator::new(&bb4, 0).unwrap();
+
+        assert_eq!(fast.run(1000), 107);
+        assert!(fast.halted());
+        assert_eq!(fast.tape().count(), 13);
+
+        for steps in [0, 1, 7, 50, 106, 107, 200] {
+            compare(&bb4, steps);
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let machines = [
+            // Counter which runs forever
+            "1RB1LB_1LA0RB",
+            // Stays inside a block forever
+            "1RB0RB_0LA1LA",
+            // Stays in place
+            "1NB0LA_0RA1NB",
+            // Five state busy beaver candidate
+            "1RB1LC_1RC1RB_1RD0LE_1LA1LD_1RH0LA",
+        ];
+
+        for table in machines {
+            let machine = parse(table).unwrap();
+
+            for steps in [1, 9, 100, 1000, 12345] {
+                compare(&machine, steps);
+            }
+        }
+
+        // Only binary machines are supported
+        let halt = Action {
+            write: vec![0],
+            moves: vec![Move::Stay],
+            next: Next::Halt,
+        };
+
+        let ternary =
+            Machine::new(vec!["A".to_string()], 3, 1, vec![vec![halt.clone(); 3]]).unwrap();
+
+        assert!(MacroSimulator::new(&ternary, 0).is_err());
+
+        // The start state must exist
+        let binary = Machine::new(vec!["A".to_string()], 2, 1, vec![vec![halt; 2]]).unwrap();
+
+        assert!(MacroSimulator::new(&binary, 0).is_ok());
+        assert_eq!(
+            MacroSimulator::new(&binary, 1).err(),
+            Some("Start state 1 doesn't exist".to_string())
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod blueprint;
+pub mod dense;
+pub mod notation;
+pub mod turing;
+pub mod vis;
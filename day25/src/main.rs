@@ -1,10 +1,9 @@
-use std::{collections::HashMap, error::Error};
+use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
-use crate::input::{input_transform, parse_program};
-
-mod input;
+pub use day25::blueprint::{parse_program, Program};
+use day25::{dense::MacroSimulator, turing::Simulator};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
@@ -13,63 +12,37 @@ fn main() -> Result<(), Box<dyn Error>> {
     let program = parse_program(&input);
 
     // Run parts
-    println!("Part 1: {}", part1(&program));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&program)?);
+    }
 
     Ok(())
 }
 
-fn part1(program: &Program) -> usize {
-    let mut cur_state = program.init_state;
-    let mut tape_pos = 0;
-    let mut tape: HashMap<isize, bool> = HashMap::new();
-
-    for _ in 0..program.diag_steps {
-        let state = program
-            .states
-            .get(&cur_state)
-            .expect("State does not exist");
-
-        let tape_val = tape.entry(tape_pos).or_insert(false);
-
-        let action = if *tape_val { &state.one } else { &state.zero };
+pub fn part1(program: &Program) -> Result<usize, String> {
+    // Binary single tape machines run much faster on the dense tape
+    let count = match MacroSimulator::new(&program.machine, program.start) {
+        Ok(mut sim) => {
+            sim.run(program.diag_steps);
 
-        *tape_val = action.write;
+            sim.tape().count()
+        }
+        Err(_) => {
+            let mut sim = Simulator::new(&program.machine, program.start)?;
 
-        tape_pos += match action.mv {
-            Dir::Left => -1,
-            Dir::Right => 1,
-        };
-
-        cur_state = action.next;
-    }
-
-    tape.values().filter(|v| **v).count()
-}
+            sim.run(program.diag_steps);
 
-#[derive(Debug)]
-pub struct Program {
-    init_state: char,
-    diag_steps: u64,
-    states: HashMap<char, State>,
-}
+            sim.tape(0).count(1)
+        }
+    };
 
-#[derive(Debug)]
-pub struct State {
-    zero: Action,
-    one: Action,
+    Ok(count)
 }
 
-#[derive(Debug)]
-pub struct Action {
-    write: bool,
-    mv: Dir,
-    next: char,
-}
+// Input parsing
 
-#[derive(Debug)]
-pub enum Dir {
-    Left,
-    Right,
+pub fn input_transform(line: String) -> String {
+    line
 }
 
 #[cfg(test)]
@@ -108,6 +81,62 @@ In state B:
 
         let program = parse_program(&input);
 
-        assert_eq!(part1(&program), 0 /* TODO */);
+        assert_eq!(part1(&program), Ok(3));
+    }
+
+    #[test]
+    fn test_general() {
+        let input = parse_test_vec(
+            "Begin in state go.
+Perform a diagnostic checksum after 10 steps.
+
+In state go:
+  If the current value is 0:
+    - Write the value 2.
+    - Move one slot to the right.
+    - Continue with state back.
+  If the current value is 1:
+    - Write the value 1.
+    - Stay in the same slot.
+    - Halt.
+  If the current value is 2:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state go.
+
+In state back:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state go.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state go.
+  If the current value is 2:
+    - Write the value 0.
+    - Stay in the same slot.
+    - Halt.
+",
+            input_transform,
+        )
+        .unwrap();
+
+        let program = parse_program(&input);
+
+        assert_eq!(program.start, 0);
+        assert_eq!(program.machine.names(), ["go", "back"]);
+        assert_eq!(program.machine.symbols(), 3);
+
+        // go writes 2, back writes 1 and returns, go turns the 2 into a 1 and moves
+        // onto the 1 which halts
+        let mut sim = Simulator::new(&program.machine, program.start).unwrap();
+
+        assert_eq!(sim.run(program.diag_steps), 4);
+        assert!(sim.halted());
+        assert_eq!(sim.tape(0).count(1), 2);
+
+        // Too many symbols for a dense tape, so runs on the general simulator
+        assert_eq!(part1(&program), Ok(2));
     }
 }
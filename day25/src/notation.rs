@@ -0,0 +1,164 @@
+use crate::turing::{Action, Machine, Move, Next, Symbol};
+
+/// Parses a machine from compact busy beaver notation, e.g. 1RB1LB_1LA1RH. States are
+/// separated by underscores and named A, B, C... Each state has an action for each
+/// symbol made of the digit to write, the move (L, R or N for none) and the next
+/// state or H to halt. --- is an undefined transition, which halts without writing or
+/// moving, so it is the same action as 0NH
+pub fn parse(table: &str) -> Result<Machine, String> {
+    let states = table.split('_').collect::<Vec<_>>();
+
+    if states.len() > 26 {
+        Err(format!("Too many states ({})", states.len()))?
+    }
+
+    let actions = states
+        .iter()
+        .map(|state| {
+            let chars = state.chars().collect::<Vec<_>>();
+
+            if chars.is_empty() || chars.len() % 3 != 0 {
+                Err(format!("Invalid state '{state}'"))?
+            }
+
+            chars
+                .chunks(3)
+                .map(|action| parse_action(action, states.len()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let symbols = actions[0].len();
+    let names = (0..actions.len()).map(name).collect();
+
+    Machine::new(names, symbols, 1, actions)
+}
+
+fn parse_action(action: &[char], states: usize) -> Result<Action, String> {
+    let invalid = || format!("Invalid action '{}'", action.iter().collect::<String>());
+
+    if action == ['-', '-', '-'] {
+        return Ok(undefined());
+    }
+
+    let write = action[0].to_digit(10).ok_or_else(invalid)? as Symbol;
+
+    let mv = match action[1] {
+        'L' => Move::Left,
+        'R' => Move::Right,
+        'N' => Move::Stay,
+        _ => Err(invalid())?,
+    };
+
+    let next = match action[2] {
+        'H' => Next::Halt,
+        c @ 'A'..='Z' if ((c as u8 - b'A') as usize) < states => {
+            Next::State((c as u8 - b'A') as usize)
+        }
+        _ => Err(invalid())?,
+    };
+
+    Ok(Action {
+        write: vec![write],
+        moves: vec![mv],
+        next,
+    })
+}
+
+/// Writes a machine in compact notation. State names aren't kept, and 0NH is written as
+/// --- since the two are the same action
+pub fn format(machine: &Machine) -> Result<String, String> {
+    if machine.tapes() != 1 || machine.symbols() > 10 || machine.states() > 26 {
+        Err("Only machines with one tape, up to ten symbols and 26 states can be written")?
+    }
+
+    let states = (0..machine.states())
+        .map(|state| {
+            (0..machine.symbols())
+                .map(|read| {
+                    let action = machine.action(state, &[read as Symbol]);
+
+                    if *action == undefined() {
+                        return "---".to_string();
+                    }
+
+                    let mv = match action.moves[0] {
+                        Move::Left => 'L',
+                        Move::Right => 'R',
+                        Move::Stay => 'N',
+                    };
+
+                    let next = match action.next {
+                        Next::State(next) => name(next),
+                        Next::Halt => "H".to_string(),
+                    };
+
+                    format!("{}{mv}{next}", action.write[0])
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    Ok(states.join("_"))
+}
+
+/// Returns the name of a state in compact notation
+pub fn name(state: usize) -> String {
+    ((b'A' + state as u8) as char).to_string()
+}
+
+fn undefined() -> Action {
+    Action {
+        write: vec![0],
+        moves: vec![Move::Stay],
+        next: Next::Halt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for table in [
+            "1RB1LB_1LA1RH",
+            "1RB1LC_1RC1RB_1RD0LE_1LA1LD_1RH0LA",
+            "1RB2LB1RH_2LA2RB1LB",
+            "1RB---_0NA1LA",
+        ] {
+            let machine = parse(table).unwrap();
+
+            assert_eq!(format(&machine).unwrap(), table);
+        }
+
+        // 0NH and --- are the same action
+        let machine = parse("1RB0NH_0NA1LA").unwrap();
+
+        assert_eq!(parse("1RB---_0NA1LA").unwrap(), machine);
+        assert_eq!(format(&machine).unwrap(), "1RB---_0NA1LA");
+
+        let machine = parse("1RB2LB1RH_2LA2RB1LB").unwrap();
+
+        assert_eq!(machine.symbols(), 3);
+        assert_eq!(machine.names(), ["A", "B"]);
+        assert_eq!(
+            machine.action(0, &[1]),
+            &Action {
+                write: vec![2],
+                moves: vec![Move::Left],
+                next: Next::State(1)
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse("").is_err());
+        assert!(parse("1RB1L").is_err());
+        assert!(parse("1RC1LB_1LA1RH").is_err());
+        assert!(parse("1XB1LB_1LA1RH").is_err());
+        assert!(parse("1RB1LB_1LA").is_err());
+        assert!(parse("2RB1LB_1LA1RH").is_err());
+    }
+}
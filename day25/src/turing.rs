@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+/// Tape symbol, from zero up to the alphabet size. Zero is blank
+pub type Symbol = u8;
+
+/// Head movement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Left,
+    Right,
+    Stay,
+}
+
+impl Move {
+    /// Returns the change in head position
+    pub fn offset(&self) -> i64 {
+        match self {
+            Move::Left => -1,
+            Move::Right => 1,
+            Move::Stay => 0,
+        }
+    }
+}
+
+/// State to continue with after an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Next {
+    State(usize),
+    Halt,
+}
+
+/// What a state does on reading a combination of symbols. write and moves have an
+/// entry for each tape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    pub write: Vec<Symbol>,
+    pub moves: Vec<Move>,
+    pub next: Next,
+}
+
+/// Turing machine definition with any number of states, symbols and tapes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine {
+    names: Vec<String>,
+    symbols: usize,
+    tapes: usize,
+    /// Actions for each state, indexed by the symbols read with the first tape's
+    /// symbol varying fastest
+    actions: Vec<Vec<Action>>,
+}
+
+impl Machine {
+    /// Creates a machine from state names and the actions for each state
+    pub fn new(
+        names: Vec<String>,
+        symbols: usize,
+        tapes: usize,
+        actions: Vec<Vec<Action>>,
+    ) -> Result<Self, String> {
+        if !(1..=Symbol::MAX as usize + 1).contains(&symbols) {
+            Err(format!("Invalid number of symbols {symbols}"))?
+        }
+
+        if tapes == 0 {
+            Err("At least one tape is needed")?
+        }
+
+        if names.len() != actions.len() {
+            Err(format!(
+                "{} state names given for {} states",
+                names.len(),
+                actions.len()
+            ))?
+        }
+
+        let combinations = symbols
+            .checked_pow(tapes as u32)
+            .ok_or("Too many symbol combinations")?;
+
+        for (name, state) in names.iter().zip(&actions) {
+            if state.len() != combinations {
+                Err(format!(
+                    "State {name} has {} actions, expecting {combinations}",
+                    state.len()
+                ))?
+            }
+
+            for action in state {
+                if action.write.len() != tapes || action.moves.len() != tapes {
+                    Err(format!(
+                        "State {name} has an action for the wrong number of tapes"
+                    ))?
+                }
+
+                if let Some(symbol) = action.write.iter().find(|s| **s as usize >= symbols) {
+                    Err(format!("State {name} writes invalid symbol {symbol}"))?
+                }
+
+                if let Next::State(next) = action.next {
+                    if next >= actions.len() {
+                        Err(format!("State {name} continues with invalid state {next}"))?
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            names,
+            symbols,
+            tapes,
+            actions,
+        })
+    }
+
+    /// Returns the state names
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the index of a state
+    pub fn state(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// Returns the number of states
+    pub fn states(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Returns the size of the alphabet
+    pub fn symbols(&self) -> usize {
+        self.symbols
+    }
+
+    /// Returns the number of tapes
+    pub fn tapes(&self) -> usize {
+        self.tapes
+    }
+
+    /// Returns an error if a state to start in doesn't exist
+    pub fn check_start(&self, start: usize) -> Result<(), String> {
+        if start >= self.states() {
+            Err(format!("Start state {start} doesn't exist"))?
+        }
+
+        Ok(())
+    }
+
+    /// Returns the action for a state on reading a symbol from each tape
+    pub fn action(&self, state: usize, read: &[Symbol]) -> &Action {
+        let index = read
+            .iter()
+            .rev()
+            .fold(0, |index, symbol| index * self.symbols + *symbol as usize);
+
+        &self.actions[state][index]
+    }
+}
+
+/// Unbounded tape which is blank to start with
+#[derive(Debug, Clone, Default)]
+pub struct Tape {
+    cells: HashMap<i64, Symbol>,
+    head: i64,
+}
+
+impl Tape {
+    /// Creates a tape holding symbols from position zero, with the head at zero
+    pub fn new(symbols: &[Symbol]) -> Self {
+        Self {
+            cells: (0..).zip(symbols.iter().copied()).collect(),
+            head: 0,
+        }
+    }
+
+    /// Returns the head position
+    pub fn head(&self) -> i64 {
+        self.head
+    }
+
+    /// Returns the symbol at a position
+    pub fn get(&self, pos: i64) -> Symbol {
+        self.cells.get(&pos).copied().unwrap_or(0)
+    }
+
+    /// Returns the symbol under the head
+    pub fn read(&self) -> Symbol {
+        self.get(self.head)
+    }
+
+    /// Writes a symbol under the head and moves the head
+    pub fn write(&mut self, symbol: Symbol, mv: Move) {
+        self.cells.insert(self.head, symbol);
+        self.head += mv.offset();
+    }
+
+    /// Returns the lowest and highest positions written, if any
+    pub fn range(&self) -> Option<(i64, i64)> {
+        let from = self.cells.keys().min()?;
+        let to = self.cells.keys().max()?;
+
+        Some((*from, *to))
+    }
+
+    /// Returns the number of written cells holding a symbol
+    pub fn count(&self, symbol: Symbol) -> usize {
+        self.cells.values().filter(|s| **s == symbol).count()
+    }
+}
+
+/// Runs a machine
+pub struct Simulator<'a> {
+    machine: &'a Machine,
+    state: Next,
+    tapes: Vec<Tape>,
+    steps: u64,
+    read: Vec<Symbol>,
+}
+
+impl<'a> Simulator<'a> {
+    /// Creates a simulator with blank tapes in the given state
+    pub fn new(machine: &'a Machine, start: usize) -> Result<Self, String> {
+        Self::with_tapes(machine, start, vec![Tape::default(); machine.tapes])
+    }
+
+    /// Creates a simulator with the given tapes in the given state
+    pub fn with_tapes(
+        machine: &'a Machine,
+        start: usize,
+        tapes: Vec<Tape>,
+    ) -> Result<Self, String> {
+        machine.check_start(start)?;
+
+        if tapes.len() != machine.tapes {
+            Err(format!(
+                "{} tapes given for a machine with {}",
+                tapes.len(),
+                machine.tapes
+            ))?
+        }
+
+        Ok(Self {
+            machine,
+            state: Next::State(start),
+            tapes,
+            steps: 0,
+            read: vec![0; machine.tapes],
+        })
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> Next {
+        self.state
+    }
+
+    /// Returns true if the machine has halted
+    pub fn halted(&self) -> bool {
+        self.state == Next::Halt
+    }
+
+    /// Returns a tape
+    pub fn tape(&self, tape: usize) -> &Tape {
+        &self.tapes[tape]
+    }
+
+    /// Returns the number of steps executed
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Executes a step, returning false if the machine has halted
+    pub fn step(&mut self) -> bool {
+        let Next::State(state) = self.state else {
+            return false;
+        };
+
+        for (read, tape) in self.read.iter_mut().zip(&self.tapes) {
+            *read = tape.read();
+        }
+
+        let action = self.machine.action(state, &self.read);
+
+        for (tape, (write, mv)) in self
+            .tapes
+            .iter_mut()
+            .zip(action.write.iter().zip(&action.moves))
+        {
+            tape.write(*write, *mv);
+        }
+
+        self.state = action.next;
+        self.steps += 1;
+
+        true
+    }
+
+    /// Runs for a number of steps or until the machine halts, returning the number
+    /// of steps executed
+    pub fn run(&mut self, steps: u64) -> u64 {
+        let start = self.steps;
+
+        for _ in 0..steps {
+            if !self.step() {
+                break;
+            }
+        }
+
+        self.steps - start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(write: &[Symbol], moves: &[Move], next: Next) -> Action {
+        Action {
+            write: write.to_vec(),
+            moves: moves.to_vec(),
+            next,
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_busy_beaver() {
+        use Move::*;
+        use Next::*;
+
+        // Two state, three symbol busy beaver champion
+        let machine = Machine::new(
+            names(&["A", "B"]),
+            3,
+            1,
+            vec![
+                vec![
+                    action(&[1], &[Right], State(1)),
+                    action(&[2], &[Left], State(1)),
+                    action(&[1], &[Right], Halt),
+                ],
+                vec![
+                    action(&[2], &[Left], State(0)),
+                    action(&[2], &[Right], State(1)),
+                    action(&[1], &[Left], State(1)),
+                ],
+            ],
+        )
+        .unwrap();
+
+        let mut sim = Simulator::new(&machine, 0).unwrap();
+
+        assert_eq!(sim.run(1000), 38);
+        assert!(sim.halted());
+        assert!(!sim.step());
+        assert_eq!(sim.tape(0).count(1) + sim.tape(0).count(2), 9);
+    }
+
+    #[test]
+    fn test_tapes() {
+        use Move::*;
+        use Next::*;
+
+        // Copies ones from the first tape to the second until a blank
+        let copy = |read: Symbol| {
+            if read == 1 {
+                action(&[1, 1], &[Right, Right], State(0))
+            } else {
+                action(&[0, 0], &[Stay, Stay], Halt)
+            }
+        };
+
+        let machine = Machine::new(
+            names(&["copy"]),
+            2,
+            2,
+            vec![(0..4).map(|i| copy((i % 2) as Symbol)).collect()],
+        )
+        .unwrap();
+
+        assert_eq!(machine.state("copy"), Some(0));
+
+        let tapes = vec![Tape::new(&[1, 1, 1]), Tape::default()];
+        let mut sim = Simulator::with_tapes(&machine, 0, tapes).unwrap();
+
+        assert_eq!(sim.run(10), 4);
+        assert_eq!(sim.state(), Halt);
+        assert_eq!(sim.tape(1).count(1), 3);
+        assert_eq!(sim.tape(0).head(), 3);
+        assert_eq!(sim.tape(1).head(), 3);
+    }
+
+    #[test]
+    fn test_invalid() {
+        use Move::*;
+        use Next::*;
+
+        let state = |next| vec![action(&[1], &[Right], next), action(&[0], &[Left], next)];
+
+        assert!(Machine::new(names(&["A"]), 2, 1, vec![state(Halt)]).is_ok());
+
+        assert_eq!(
+            Machine::new(names(&["A"]), 2, 1, vec![state(State(1))]),
+            Err("State A continues with invalid state 1".to_string())
+        );
+
+        assert_eq!(
+            Machine::new(names(&["A"]), 3, 1, vec![state(Halt)]),
+            Err("State A has 2 actions, expecting 3".to_string())
+        );
+
+        assert_eq!(
+            Machine::new(names(&["A"]), 1, 1, vec![vec![action(&[1], &[Stay], Halt)]]),
+            Err("State A writes invalid symbol 1".to_string())
+        );
+
+        assert!(Machine::new(names(&["A"]), 2, 2, vec![state(Halt)]).is_err());
+        assert!(Machine::new(names(&["A"]), 2, 0, vec![state(Halt)]).is_err());
+        assert!(Machine::new(names(&["A", "B"]), 2, 1, vec![state(Halt)]).is_err());
+
+        // Start state out of range
+        let machine = Machine::new(names(&["A"]), 2, 1, vec![state(Halt)]).unwrap();
+
+        assert_eq!(
+            Simulator::new(&machine, 1).err(),
+            Some("Start state 1 doesn't exist".to_string())
+        );
+        assert!(Simulator::with_tapes(&machine, 0, vec![]).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::turing::{Machine, Simulator, Symbol};
+
+/// Snapshot of a tape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    /// Steps executed when the snapshot was taken
+    pub step: u64,
+    /// Position of the first cell
+    pub start: i64,
+    pub cells: Vec<Symbol>,
+    pub head: i64,
+}
+
+/// Runs a machine for a number of steps, or until it halts, taking a snapshot of
+/// the first tape at the start and after every interval. An interval of zero is taken
+/// as one
+pub fn record(
+    machine: &Machine,
+    start: usize,
+    steps: u64,
+    interval: u64,
+) -> Result<Vec<Row>, String> {
+    let interval = interval.max(1);
+    let mut sim = Simulator::new(machine, start)?;
+    let mut rows = vec![snapshot(&sim)];
+
+    while sim.steps() < steps && !sim.halted() {
+        sim.run(interval.min(steps - sim.steps()));
+        rows.push(snapshot(&sim));
+    }
+
+    Ok(rows)
+}
+
+fn snapshot(sim: &Simulator) -> Row {
+    let tape = sim.tape(0);
+    let head = tape.head();
+
+    let (from, to) = match tape.range() {
+        Some((from, to)) => (from.min(head), to.max(head)),
+        None => (head, head),
+    };
+
+    Row {
+        step: sim.steps(),
+        start: from,
+        cells: (from..=to).map(|pos| tape.get(pos)).collect(),
+        head,
+    }
+}
+
+/// Space-time diagram with a row of pixels for each snapshot, where each pixel is
+/// a palette index
+pub struct Diagram {
+    pub width: usize,
+    /// Number of cells shown by each pixel
+    pub cells_per_pixel: usize,
+    pub pixels: Vec<Vec<u8>>,
+}
+
+/// Draws snapshots one above the other, squeezing several cells into a pixel if the
+/// tape is wider than the maximum width. Pixels are coloured by the highest symbol
+/// in them, with the head drawn in the colour after the last symbol
+pub fn render(rows: &[Row], symbols: usize, max_width: usize) -> Diagram {
+    let from = rows.iter().map(|r| r.start).min().unwrap_or(0);
+    let to = rows
+        .iter()
+        .map(|r| r.start + r.cells.len() as i64)
+        .max()
+        .unwrap_or(0);
+
+    let cells = (to - from) as usize;
+    let cells_per_pixel = cells.div_ceil(max_width).max(1);
+    let width = cells.div_ceil(cells_per_pixel);
+    let head_colour = symbols as u8;
+
+    let pixels = rows
+        .iter()
+        .map(|row| {
+            let mut line = vec![0; width];
+
+            for (i, symbol) in row.cells.iter().enumerate() {
+                let x = (row.start - from) as usize + i;
+                let pixel = &mut line[x / cells_per_pixel];
+
+                *pixel = (*pixel).max(*symbol);
+            }
+
+            line[(row.head - from) as usize / cells_per_pixel] = head_colour;
+
+            line
+        })
+        .collect();
+
+    Diagram {
+        width,
+        cells_per_pixel,
+        pixels,
+    }
+}
+
+/// Returns a palette with black for blank cells, a colour for each other symbol and
+/// red for the head
+pub fn palette(symbols: usize) -> Vec<[u8; 3]> {
+    const COLOURS: [[u8; 3]; 6] = [
+        [0xff, 0xff, 0xff],
+        [0x40, 0x80, 0xff],
+        [0x40, 0xc0, 0x40],
+        [0xff, 0xc0, 0x20],
+        [0xc0, 0x40, 0xff],
+        [0x40, 0xe0, 0xe0],
+    ];
+
+    let mut palette = vec![[0, 0, 0]];
+
+    palette.extend((1..symbols).map(|s| COLOURS[(s - 1) % COLOURS.len()]));
+    palette.push([0xff, 0x20, 0x20]);
+
+    palette
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notation::parse;
+
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        // Busy beaver with two states
+        let machine = parse("1RB1LB_1LA1RH").unwrap();
+
+        let rows = record(&machine, 0, 100, 2).unwrap();
+
+        // Halts after 6 steps
+        assert_eq!(
+            rows.iter().map(|r| r.step).collect::<Vec<_>>(),
+            vec![0, 2, 4, 6]
+        );
+
+        // A zero interval snapshots every step rather than never finishing
+        assert_eq!(record(&machine, 0, 3, 0).unwrap().len(), 4);
+
+        assert_eq!(
+            rows[3],
+            Row {
+                step: 6,
+                start: -2,
+                cells: vec![1, 1, 1, 1],
+                head: 0
+            }
+        );
+
+        let diagram = render(&rows, 2, 100);
+
+        assert_eq!(diagram.width, 4);
+        assert_eq!(diagram.cells_per_pixel, 1);
+        assert_eq!(
+            diagram.pixels,
+            vec![
+                vec![0, 0, 2, 0],
+                vec![0, 0, 2, 1],
+                vec![2, 1, 1, 1],
+                vec![1, 1, 2, 1],
+            ]
+        );
+
+        // Two cells to a pixel
+        let diagram = render(&rows, 2, 3);
+
+        assert_eq!(diagram.width, 2);
+        assert_eq!(diagram.cells_per_pixel, 2);
+        assert_eq!(diagram.pixels[1], vec![0, 2]);
+        assert_eq!(diagram.pixels[2], vec![2, 1]);
+
+        assert_eq!(palette(2).len(), 3);
+    }
+}
@@ -0,0 +1,101 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc::input::parse_input_vec;
+use duet::{
+    compile::{Bytecode, BytecodeVm},
+    instruction::{Instruction, Reg},
+    io::{Queue, Sound},
+    optimise::{optimise, OptimisedVm},
+    vm::{Machine, NoIo, Op, Vm},
+};
+
+fn sound<O: Op>(mut vm: Machine<O>) -> i64 {
+    let mut sound = Sound::default();
+
+    vm.run(&mut sound);
+
+    sound.last_freq
+}
+
+fn duet<'a, O: Op + 'a>(new: impl Fn() -> Machine<'a, O>) -> usize {
+    let p = Reg::new('p').unwrap();
+
+    let mut vms = [new(), new()];
+    let mut queues = [Queue::default(), Queue::default()];
+    let mut sent = 0;
+
+    vms[1].set_reg(p, 1);
+
+    loop {
+        for i in 0..2 {
+            vms[i].run(&mut queues[i]);
+
+            let output = std::mem::take(&mut queues[i].output);
+
+            if i == 1 {
+                sent += output.len();
+            }
+
+            queues[1 - i].input.extend(output);
+        }
+
+        if queues[0].input.is_empty() {
+            break sent;
+        }
+    }
+}
+
+fn coprocessor<O: Op>(mut vm: Machine<O>, a: i64) -> i64 {
+    vm.set_reg(Reg::new('a').unwrap(), a);
+    vm.run(&mut NoIo);
+    vm.reg(Reg::new('h').unwrap())
+}
+
+fn bench(c: &mut Criterion) {
+    let transform = |line: String| line.parse::<Instruction>().unwrap();
+
+    // Day 18
+    let program = parse_input_vec(18, transform).unwrap();
+    let bytecode = Bytecode::compile(&program);
+
+    let mut group = c.benchmark_group("day18");
+
+    group.bench_function("part1_interpreter", |b| b.iter(|| sound(Vm::new(&program))));
+    group.bench_function("part1_bytecode", |b| {
+        b.iter(|| sound(BytecodeVm::new(&bytecode)))
+    });
+    group.bench_function("part2_interpreter", |b| {
+        b.iter(|| duet(|| Vm::new(&program)))
+    });
+    group.bench_function("part2_bytecode", |b| {
+        b.iter(|| duet(|| BytecodeVm::new(&bytecode)))
+    });
+
+    group.finish();
+
+    // Day 23
+    let program = parse_input_vec(23, transform).unwrap();
+    let bytecode = Bytecode::compile(&program);
+    let optimised = optimise(&program);
+    let optimised_bytecode = Bytecode::compile_optimised(&optimised);
+
+    let mut group = c.benchmark_group("day23");
+
+    group.bench_function("part1_interpreter", |b| {
+        b.iter(|| coprocessor(Vm::new(&program), 0))
+    });
+    group.bench_function("part1_bytecode", |b| {
+        b.iter(|| coprocessor(BytecodeVm::new(&bytecode), 0))
+    });
+    group.bench_function("part2_optimised_interpreter", |b| {
+        b.iter(|| coprocessor(OptimisedVm::new(&optimised), 1))
+    });
+    group.bench_function("part2_optimised_bytecode", |b| {
+        b.iter(|| coprocessor(BytecodeVm::new(&optimised_bytecode), 1))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
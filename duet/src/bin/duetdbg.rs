@@ -0,0 +1,41 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{stdin, stdout, IsTerminal},
+    process::exit,
+};
+
+use duet::{
+    debugger::Debugger,
+    instruction::{parse_program, parse_setting},
+    vm::Vm,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    if args.is_empty() {
+        eprintln!("Usage: duetdbg <program file> [reg=value...]");
+        exit(1);
+    }
+
+    // Load the program
+    let program = parse_program(&fs::read_to_string(&args[0])?)?;
+
+    let mut vm = Vm::new(&program);
+
+    // Set initial register values
+    for arg in &args[1..] {
+        let (reg, value) = parse_setting(arg)?;
+
+        vm.set_reg(reg, value);
+    }
+
+    // Run the debugger on stdin
+    let mut debugger = Debugger::new(vm);
+
+    debugger.repl(stdin().lock(), &mut stdout(), stdin().is_terminal())?;
+
+    Ok(())
+}
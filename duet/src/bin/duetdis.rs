@@ -0,0 +1,51 @@
+use std::{env, error::Error, fs, process::exit};
+
+use duet::{
+    decompile::decompile,
+    disasm::Disassembly,
+    instruction::parse_program,
+    optimise::{optimise, Op},
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+
+    // Options
+    let mut option = |name: &str| match args.iter().position(|a| a == name) {
+        Some(pos) => {
+            args.remove(pos);
+            true
+        }
+        None => false,
+    };
+
+    let dot = option("--dot");
+    let pseudo = option("--decompile");
+    let optimised = option("--optimise");
+
+    if args.len() != 1 {
+        eprintln!("Usage: duetdis <program file> [--dot | --decompile] [--optimise]");
+        exit(1);
+    }
+
+    // Load the program
+    let program = parse_program(&fs::read_to_string(&args[0])?)?;
+
+    let program = if optimised {
+        optimise(&program).ops().to_vec()
+    } else {
+        program.into_iter().map(Op::from).collect()
+    };
+
+    let disassembly = Disassembly::new(&program);
+
+    if dot {
+        print!("{}", disassembly.dot());
+    } else if pseudo {
+        print!("{}", decompile(&program));
+    } else {
+        print!("{}", disassembly.listing());
+    }
+
+    Ok(())
+}
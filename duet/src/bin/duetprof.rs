@@ -0,0 +1,53 @@
+use std::{env, error::Error, fs, process::exit};
+
+use duet::{
+    instruction::{parse_program, parse_setting},
+    profile::Profile,
+    vm::{NoIo, Vm},
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+
+    // Optional step limit
+    let mut max_steps = None;
+
+    if let Some(pos) = args.iter().position(|a| a == "--max-steps") {
+        let value = args
+            .get(pos + 1)
+            .ok_or("--max-steps needs a value")?
+            .parse::<u64>()?;
+
+        max_steps = Some(value);
+        args.drain(pos..=pos + 1);
+    }
+
+    if args.is_empty() {
+        eprintln!("Usage: duetprof <program file> [--max-steps n] [reg=value...]");
+        exit(1);
+    }
+
+    // Load the program
+    let program = parse_program(&fs::read_to_string(&args[0])?)?;
+
+    let mut vm = Vm::new(&program);
+
+    // Set initial register values
+    for arg in &args[1..] {
+        let (reg, value) = parse_setting(arg)?;
+
+        vm.set_reg(reg, value);
+    }
+
+    // Run the program collecting a profile
+    let (profile, stop) = Profile::run(&mut vm, &mut NoIo, max_steps);
+
+    print!("{}", profile.listing(&program));
+
+    match stop {
+        Some(stop) => println!("Stopped ({stop:?}) at ip {}", vm.ip()),
+        None => println!("Step limit reached at ip {}", vm.ip()),
+    }
+
+    Ok(())
+}
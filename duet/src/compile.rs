@@ -0,0 +1,355 @@
+use crate::{
+    instruction::{Instruction, Reg, RegImm, REGISTERS},
+    optimise::{self, Fused, Optimised},
+    vm::{self, modulo, rcv, store, Io, Machine, Stop},
+};
+
+/// Bytecode operation. Registers are resolved to register file slots, operand kinds are
+/// resolved to separate operations and jump targets are absolute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    SndR(u8),
+    SndI(i64),
+    SetR(u8, u8),
+    SetI(u8, i64),
+    AddR(u8, u8),
+    AddI(u8, i64),
+    SubR(u8, u8),
+    SubI(u8, i64),
+    MulR(u8, u8),
+    MulI(u8, i64),
+    ModR(u8, u8),
+    ModI(u8, i64),
+    Rcv(u8),
+    /// Unconditional jump
+    Jump(i64),
+    /// Jump if the register is greater than zero
+    Jgz(u8, i64),
+    /// Jump if the register is not zero
+    Jnz(u8, i64),
+    /// Jump with a register offset
+    JgzRel(RegImm, u8),
+    JnzRel(RegImm, u8),
+    /// Jump which is never taken
+    Nop,
+    Fused(Fused),
+}
+
+impl Op {
+    /// Compiles the instruction at the given address
+    fn new(instruction: Instruction, addr: usize) -> Self {
+        use RegImm::{Imm, Reg as R};
+
+        let slot = |reg: Reg| reg.index() as u8;
+        let target = |offset: i64| (addr as i64).saturating_add(offset);
+
+        match instruction {
+            Instruction::Snd(R(x)) => Op::SndR(slot(x)),
+            Instruction::Snd(Imm(x)) => Op::SndI(x),
+            Instruction::Set(x, R(y)) => Op::SetR(slot(x), slot(y)),
+            Instruction::Set(x, Imm(y)) => Op::SetI(slot(x), y),
+            Instruction::Add(x, R(y)) => Op::AddR(slot(x), slot(y)),
+            Instruction::Add(x, Imm(y)) => Op::AddI(slot(x), y),
+            Instruction::Sub(x, R(y)) => Op::SubR(slot(x), slot(y)),
+            Instruction::Sub(x, Imm(y)) => Op::SubI(slot(x), y),
+            Instruction::Mul(x, R(y)) => Op::MulR(slot(x), slot(y)),
+            Instruction::Mul(x, Imm(y)) => Op::MulI(slot(x), y),
+            Instruction::Mod(x, R(y)) => Op::ModR(slot(x), slot(y)),
+            Instruction::Mod(x, Imm(y)) => Op::ModI(slot(x), y),
+            Instruction::Rcv(x) => Op::Rcv(slot(x)),
+            Instruction::Jgz(Imm(x), Imm(y)) if x > 0 => Op::Jump(target(y)),
+            Instruction::Jnz(Imm(x), Imm(y)) if x != 0 => Op::Jump(target(y)),
+            Instruction::Jgz(Imm(_), Imm(_)) | Instruction::Jnz(Imm(_), Imm(_)) => Op::Nop,
+            Instruction::Jgz(R(x), Imm(y)) => Op::Jgz(slot(x), target(y)),
+            Instruction::Jnz(R(x), Imm(y)) => Op::Jnz(slot(x), target(y)),
+            Instruction::Jgz(x, R(y)) => Op::JgzRel(x, slot(y)),
+            Instruction::Jnz(x, R(y)) => Op::JnzRel(x, slot(y)),
+        }
+    }
+}
+
+/// Program compiled to bytecode. Each instruction compiles to one operation so
+/// addresses are unchanged
+pub struct Bytecode {
+    program: Vec<Instruction>,
+    ops: Vec<Op>,
+}
+
+impl Bytecode {
+    /// Compiles a program
+    pub fn compile(program: &[Instruction]) -> Self {
+        Self {
+            program: program.to_vec(),
+            ops: program
+                .iter()
+                .enumerate()
+                .map(|(addr, instruction)| Op::new(*instruction, addr))
+                .collect(),
+        }
+    }
+
+    /// Compiles an optimised program, keeping its fused operations
+    pub fn compile_optimised(optimised: &Optimised) -> Self {
+        Self {
+            program: optimised.program().to_vec(),
+            ops: optimised
+                .ops()
+                .iter()
+                .enumerate()
+                .map(|(addr, op)| match *op {
+                    optimise::Op::Instruction(instruction) => Op::new(instruction, addr),
+                    optimise::Op::Fused(fused) => Op::Fused(fused),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the program the bytecode was compiled from
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    /// Returns the number of operations
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns true if the program is empty
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+impl vm::Op for Op {
+    #[inline(always)]
+    fn execute<I: Io + ?Sized>(
+        self,
+        r: &mut [i64; REGISTERS],
+        ip: i64,
+        io: &mut I,
+    ) -> Result<i64, Stop> {
+        let value = |r: &[i64; REGISTERS], regimm: RegImm| match regimm {
+            RegImm::Reg(reg) => r[reg.index()],
+            RegImm::Imm(imm) => imm,
+        };
+
+        match self {
+            Op::SndR(x) => io.snd(r[x as usize]),
+            Op::SndI(x) => io.snd(x),
+            Op::SetR(x, y) => r[x as usize] = r[y as usize],
+            Op::SetI(x, y) => r[x as usize] = y,
+            Op::AddR(x, y) => {
+                let value = r[x as usize].checked_add(r[y as usize]);
+                return store(r, x as usize, value, ip);
+            }
+            Op::AddI(x, y) => {
+                let value = r[x as usize].checked_add(y);
+                return store(r, x as usize, value, ip);
+            }
+            Op::SubR(x, y) => {
+                let value = r[x as usize].checked_sub(r[y as usize]);
+                return store(r, x as usize, value, ip);
+            }
+            Op::SubI(x, y) => {
+                let value = r[x as usize].checked_sub(y);
+                return store(r, x as usize, value, ip);
+            }
+            Op::MulR(x, y) => {
+                let value = r[x as usize].checked_mul(r[y as usize]);
+                return store(r, x as usize, value, ip);
+            }
+            Op::MulI(x, y) => {
+                let value = r[x as usize].checked_mul(y);
+                return store(r, x as usize, value, ip);
+            }
+            Op::ModR(x, y) => {
+                let y = r[y as usize];
+                return modulo(r, x as usize, y, ip);
+            }
+            Op::ModI(x, y) => return modulo(r, x as usize, y, ip),
+            Op::Rcv(x) => return rcv(r, x as usize, ip, io),
+            Op::Jump(target) => return Ok(target),
+            Op::Jgz(x, target) => {
+                if r[x as usize] > 0 {
+                    return Ok(target);
+                }
+            }
+            Op::Jnz(x, target) => {
+                if r[x as usize] != 0 {
+                    return Ok(target);
+                }
+            }
+            Op::JgzRel(x, y) => {
+                if value(r, x) > 0 {
+                    return Ok(ip.saturating_add(r[y as usize]));
+                }
+            }
+            Op::JnzRel(x, y) => {
+                if value(r, x) != 0 {
+                    return Ok(ip.saturating_add(r[y as usize]));
+                }
+            }
+            Op::Nop => (),
+            Op::Fused(fused) => match fused.execute(r) {
+                Some(size) => return Ok(ip + size as i64),
+                None => return Op::new(fused.fallback(), ip as usize).execute(r, ip, io),
+            },
+        }
+
+        Ok(ip + 1)
+    }
+}
+
+/// Virtual machine running a compiled program
+pub type BytecodeVm<'a> = Machine<'a, Op>;
+
+impl<'a> BytecodeVm<'a> {
+    /// Creates a new virtual machine with all registers zeroed
+    pub fn new(bytecode: &'a Bytecode) -> Self {
+        Self::with_ops(&bytecode.program, &bytecode.ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        instruction::parse_program,
+        io::Queue,
+        optimise::optimise,
+        vm::{Halt, NoIo, Step, Vm},
+    };
+
+    use super::*;
+
+    /// Runs a program on both virtual machines and checks they behave the same
+    fn compare(listing: &str, setup: &[(char, i64)], input: &[i64]) -> (Stop, Vec<i64>) {
+        let program = parse_program(listing).unwrap();
+        let bytecode = Bytecode::compile(&program);
+
+        assert_eq!(bytecode.len(), program.len());
+
+        let mut vm = Vm::new(&program);
+        let mut bvm = BytecodeVm::new(&bytecode);
+
+        for (reg, value) in setup {
+            vm.set_reg(Reg::new(*reg).unwrap(), *value);
+            bvm.set_reg(Reg::new(*reg).unwrap(), *value);
+        }
+
+        let mut io = Queue::default();
+        let mut bio = Queue::default();
+
+        io.input.extend(input);
+        bio.input.extend(input);
+
+        let stop = vm.run(&mut io);
+
+        assert_eq!(bvm.run(&mut bio), stop);
+        assert_eq!(bvm.registers(), vm.registers());
+        assert_eq!(bvm.ip(), vm.ip());
+        assert_eq!(bvm.steps(), vm.steps());
+        assert_eq!(bvm.halted(), vm.halted());
+        assert_eq!(bio.output, io.output);
+
+        (stop, io.output)
+    }
+
+    #[test]
+    fn test_compare() {
+        // Arithmetic and conditional jumps
+        let (stop, _) = compare(
+            "set a 10
+add b a
+sub a 1
+jnz a -2
+mul b 2
+mod b 7
+set c b
+mul c c
+sub c 3
+mod c b
+",
+            &[],
+            &[],
+        );
+        assert_eq!(stop, Stop::Halted(Halt::Finished));
+
+        // Constant jumps, register offsets and io
+        let (stop, output) = compare(
+            "set o 2
+rcv a
+jgz 0 10
+jnz 0 10
+jnz 1 2
+snd 99
+snd a
+sub o 1
+set j -8
+jgz o j
+rcv a
+",
+            &[],
+            &[5, 6],
+        );
+        assert_eq!(stop, Stop::Blocked);
+        assert_eq!(output, vec![5, 6]);
+
+        // Jump out of the program backwards
+        let (stop, _) = compare("jgz p -3", &[('p', 1)], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::JumpedOut(-3)));
+
+        // Jumps with huge offsets
+        let (stop, _) = compare("jgz 1 a", &[('a', i64::MIN)], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::JumpedOut(i64::MIN)));
+
+        let (stop, _) = compare("jgz 1 9223372036854775807", &[], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::JumpedOut(i64::MAX)));
+
+        // Faults
+        let (stop, _) = compare("set a 7\nmod a b", &[], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::DivideByZero(1)));
+
+        let (stop, _) = compare("mod a 0", &[], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::DivideByZero(0)));
+
+        let (stop, _) = compare("snd 1\nadd a b", &[('a', i64::MAX), ('b', 1)], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::Overflow(1)));
+
+        let (stop, _) = compare("sub a 1", &[('a', i64::MIN)], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::Overflow(0)));
+
+        let (stop, _) = compare("mul a a\njnz 1 -1", &[('a', 3)], &[]);
+        assert_eq!(stop, Stop::Halted(Halt::Overflow(0)));
+
+        // rcv halting
+        let program = parse_program("snd 1\nrcv a\nsnd 2").unwrap();
+        let bytecode = Bytecode::compile(&program);
+        let mut bvm = BytecodeVm::new(&bytecode);
+
+        assert_eq!(bvm.run(&mut NoIo), Stop::Halted(Halt::Received));
+        assert_eq!(bvm.steps(), 2);
+        assert_eq!(bvm.step(&mut NoIo), Step::Halted(Halt::Received));
+    }
+
+    #[test]
+    fn test_fused() {
+        let program = parse_program("add a 3\nsub c 1\njnz c -2").unwrap();
+        let optimised = optimise(&program);
+        let bytecode = Bytecode::compile_optimised(&optimised);
+
+        // Fused loop
+        let mut bvm = BytecodeVm::new(&bytecode);
+        bvm.set_reg(Reg::new('c').unwrap(), 5);
+
+        assert_eq!(bvm.run(&mut NoIo), Stop::Halted(Halt::Finished));
+        assert_eq!(bvm.reg(Reg::new('a').unwrap()), 15);
+        assert_eq!(bvm.steps(), 1);
+
+        // Fallback
+        let mut bvm = BytecodeVm::new(&bytecode);
+
+        assert_eq!(bvm.step(&mut NoIo), Step::Executed(0));
+        assert_eq!(bvm.reg(Reg::new('a').unwrap()), 3);
+        assert_eq!(bvm.ip(), 1);
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, BufRead, Write},
+};
+
+use crate::{
+    instruction::Reg,
+    io::Queue,
+    vm::{Step, Vm},
+};
+
+const HELP: &str = "Commands:
+  s, step [n]         Execute n instructions (default 1)
+  c, continue         Run until a breakpoint, watchpoint, block or halt
+  b, break <addr>     Set a breakpoint on an instruction address
+  d, delete <addr>    Delete a breakpoint
+  w, watch <reg>      Stop when a register changes
+  u, unwatch <reg>    Delete a watchpoint
+  r, regs             Print the registers
+  ip                  Print the instruction pointer
+  l, list             List the program
+  set <reg> <value>   Set a register
+  in <value>...       Queue values for rcv
+  out                 Print the values sent by snd
+  q, quit             Exit the debugger";
+
+/// Interactive debugger for a program
+pub struct Debugger<'a> {
+    vm: Vm<'a>,
+    /// snd values are collected and rcv takes values queued by the user
+    io: Queue,
+    breakpoints: BTreeSet<usize>,
+    watches: BTreeSet<Reg>,
+    used: BTreeSet<Reg>,
+}
+
+impl<'a> Debugger<'a> {
+    /// Creates a new debugger for a virtual machine
+    pub fn new(vm: Vm<'a>) -> Self {
+        // Find registers used by the program
+        let used = vm
+            .program()
+            .iter()
+            .flat_map(|i| i.registers())
+            .collect::<BTreeSet<_>>();
+
+        Self {
+            vm,
+            io: Queue::default(),
+            breakpoints: BTreeSet::new(),
+            watches: BTreeSet::new(),
+            used,
+        }
+    }
+
+    /// Returns the virtual machine
+    pub fn vm(&self) -> &Vm<'a> {
+        &self.vm
+    }
+
+    /// Reads commands until the input is exhausted or quit is entered
+    pub fn repl<R: BufRead, W: Write>(
+        &mut self,
+        input: R,
+        out: &mut W,
+        prompt: bool,
+    ) -> io::Result<()> {
+        let mut lines = input.lines();
+
+        loop {
+            if prompt {
+                write!(out, "{:>4}> ", self.vm.ip())?;
+                out.flush()?;
+            }
+
+            let Some(line) = lines.next() else {
+                break;
+            };
+
+            if !self.command(&line?, out)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Executes a single command. Returns false if the debugger should exit
+    pub fn command<W: Write>(&mut self, line: &str, out: &mut W) -> io::Result<bool> {
+        let mut terms = line.split_whitespace();
+
+        let Some(command) = terms.next() else {
+            return Ok(true);
+        };
+
+        let args = terms.collect::<Vec<_>>();
+
+        match (command, args.as_slice()) {
+            ("s" | "step", []) => self.step(1, out)?,
+            ("s" | "step", [n]) => match n.parse::<u64>() {
+                Ok(n) => self.step(n, out)?,
+                Err(_) => writeln!(out, "Invalid step count {n}")?,
+            },
+            ("c" | "continue", []) => self.cont(out)?,
+            ("b" | "break", [addr]) => match self.parse_addr(addr) {
+                Some(addr) => {
+                    self.breakpoints.insert(addr);
+                    writeln!(out, "Breakpoint at {}", self.location(addr))?;
+                }
+                None => writeln!(out, "Invalid address {addr}")?,
+            },
+            ("d" | "delete", [addr]) => match self.parse_addr(addr) {
+                Some(addr) if self.breakpoints.remove(&addr) => {
+                    writeln!(out, "Deleted breakpoint at {addr}")?
+                }
+                _ => writeln!(out, "No breakpoint at {addr}")?,
+            },
+            ("w" | "watch", [reg]) => match reg.parse::<Reg>() {
+                Ok(reg) => {
+                    self.watches.insert(reg);
+                    writeln!(out, "Watching {reg} = {}", self.vm.reg(reg))?;
+                }
+                Err(e) => writeln!(out, "{e}")?,
+            },
+            ("u" | "unwatch", [reg]) => match reg.parse::<Reg>() {
+                Ok(reg) if self.watches.remove(&reg) => writeln!(out, "Deleted watch on {reg}")?,
+                _ => writeln!(out, "No watch on {reg}")?,
+            },
+            ("r" | "regs", []) => self.print_regs(out)?,
+            ("ip", []) => self.print_ip(out)?,
+            ("l" | "list", []) => {
+                for addr in 0..self.vm.program().len() {
+                    let marker = if addr as i64 == self.vm.ip() {
+                        "=>"
+                    } else {
+                        "  "
+                    };
+                    let bp = if self.breakpoints.contains(&addr) {
+                        "*"
+                    } else {
+                        " "
+                    };
+
+                    writeln!(out, "{marker}{bp}{}", self.location(addr))?;
+                }
+            }
+            ("set", [reg, value]) => match (reg.parse::<Reg>(), value.parse::<i64>()) {
+                (Ok(reg), Ok(value)) => {
+                    self.vm.set_reg(reg, value);
+                    writeln!(out, "{reg} = {value}")?;
+                }
+                _ => writeln!(out, "Usage: set <reg> <value>")?,
+            },
+            ("in", values) if !values.is_empty() => {
+                match values
+                    .iter()
+                    .map(|v| v.parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(values) => self.io.input.extend(values),
+                    Err(_) => writeln!(out, "Invalid value")?,
+                }
+            }
+            ("out", []) => writeln!(
+                out,
+                "{}",
+                self.io
+                    .output
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )?,
+            ("h" | "help", []) => writeln!(out, "{HELP}")?,
+            ("q" | "quit", []) => return Ok(false),
+            _ => writeln!(out, "Invalid command '{line}' - enter help for commands")?,
+        }
+
+        Ok(true)
+    }
+
+    fn step<W: Write>(&mut self, count: u64, out: &mut W) -> io::Result<()> {
+        for _ in 0..count {
+            if !self.single_step(out)? {
+                return Ok(());
+            }
+        }
+
+        self.print_ip(out)
+    }
+
+    fn cont<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        loop {
+            if !self.single_step(out)? {
+                return Ok(());
+            }
+
+            if let Ok(ip) = usize::try_from(self.vm.ip()) {
+                if self.breakpoints.contains(&ip) {
+                    writeln!(out, "Breakpoint at {}", self.location(ip))?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Executes one instruction, reporting watchpoints and stops. Returns false if execution should stop
+    fn single_step<W: Write>(&mut self, out: &mut W) -> io::Result<bool> {
+        let before = *self.vm.registers();
+
+        match self.vm.step(&mut self.io) {
+            Step::Executed(addr) => {
+                let mut hit = false;
+
+                for reg in &self.watches {
+                    let (old, new) = (before[reg.index()], self.vm.reg(*reg));
+
+                    if old != new {
+                        writeln!(
+                            out,
+                            "Watch {reg}: {old} -> {new} at {}",
+                            self.location(addr)
+                        )?;
+                        hit = true;
+                    }
+                }
+
+                Ok(!hit)
+            }
+            Step::Blocked => {
+                writeln!(out, "Blocked at {}", self.location(self.vm.ip() as usize))?;
+                Ok(false)
+            }
+            Step::Halted(halt) => {
+                writeln!(
+                    out,
+                    "Halted ({halt}) at ip {} after {} steps",
+                    self.vm.ip(),
+                    self.vm.steps()
+                )?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn print_regs<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        // Show registers the program uses and any others which are non-zero
+        let regs = (0..26u8)
+            .map(|i| Reg::new((b'a' + i) as char).unwrap())
+            .filter(|reg| self.used.contains(reg) || self.vm.reg(*reg) != 0)
+            .map(|reg| format!("{reg}={}", self.vm.reg(reg)))
+            .collect::<Vec<_>>();
+
+        writeln!(out, "{}", regs.join(" "))
+    }
+
+    fn print_ip<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match usize::try_from(self.vm.ip()) {
+            Ok(ip) if ip < self.vm.program().len() => writeln!(out, "{}", self.location(ip)),
+            _ => writeln!(out, "ip {} is outside the program", self.vm.ip()),
+        }
+    }
+
+    fn location(&self, addr: usize) -> String {
+        match self.vm.program().get(addr) {
+            Some(instruction) => format!("{addr:>4}: {instruction}"),
+            None => format!("{addr:>4}"),
+        }
+    }
+
+    fn parse_addr(&self, addr: &str) -> Option<usize> {
+        addr.parse::<usize>()
+            .ok()
+            .filter(|addr| *addr < self.vm.program().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::parse_program;
+
+    use super::*;
+
+    const PROGRAM: &str = "set a 3
+add b a
+sub a 1
+jnz a -2
+rcv c
+snd c
+";
+
+    fn run(script: &str) -> String {
+        let program = parse_program(PROGRAM).unwrap();
+        let mut debugger = Debugger::new(Vm::new(&program));
+        let mut out = Vec::new();
+
+        debugger.repl(script.as_bytes(), &mut out, false).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_step() {
+        assert_eq!(
+            run("ip\ns\ns 2\nregs\nq\ns\n"),
+            "   0: set a 3
+   1: add b a
+   3: jnz a -2
+a=2 b=3 c=0
+"
+        );
+    }
+
+    #[test]
+    fn test_break_watch() {
+        assert_eq!(
+            run("b 4\nc\nregs\nw c\nc\nin 42\nc\nc\nout\n"),
+            "Breakpoint at    4: rcv c
+Breakpoint at    4: rcv c
+a=0 b=6 c=0
+Watching c = 0
+Blocked at    4: rcv c
+Watch c: 0 -> 42 at    4: rcv c
+Halted (finished) at ip 6 after 12 steps
+42
+"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            run("b 10\nw A\nfoo\n"),
+            "Invalid address 10
+Invalid register A
+Invalid command 'foo' - enter help for commands
+"
+        );
+    }
+}
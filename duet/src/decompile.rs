@@ -0,0 +1,631 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    disasm::{jump_target, successors, Disassembly},
+    instruction::{Instruction, Reg, RegImm},
+    optimise::Op,
+};
+
+/// Decompiles a program to structured pseudo-code.
+///
+/// Backward jumps become loops, forward jumps over code become ifs, and jumps to the
+/// end of a loop or out of the program become break and exit. A temporary register
+/// which is computed just before a conditional jump and not used afterwards is folded
+/// in to the condition. Jumps which don't fit these shapes are left as gotos.
+/// Fused operations are shown as calls, assuming their preconditions hold
+pub fn decompile(program: &[Op]) -> String {
+    let mut decompiler = Decompiler::new(program);
+
+    decompiler.block(0, program.len(), 0, None);
+    decompiler.render()
+}
+
+/// Comparison for a conditional jump
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cond {
+    lhs: String,
+    op: &'static str,
+    rhs: String,
+}
+
+impl Cond {
+    fn negate(&self) -> Self {
+        let op = match self.op {
+            "!=" => "==",
+            "==" => "!=",
+            ">" => "<=",
+            _ => ">",
+        };
+
+        Self {
+            lhs: self.lhs.clone(),
+            op,
+            rhs: self.rhs.clone(),
+        }
+    }
+
+    fn text(&self) -> String {
+        format!("{} {} {}", self.lhs, self.op, self.rhs)
+    }
+}
+
+/// Condition under which a jump is taken
+enum JumpCond {
+    Always,
+    Never,
+    /// Taken when the condition holds. The condition is computed from the given address
+    When(usize, Cond),
+}
+
+/// Innermost loop being decompiled
+#[derive(Clone, Copy)]
+struct Loop {
+    head: usize,
+    exit: usize,
+}
+
+struct Line {
+    addr: usize,
+    depth: usize,
+    text: String,
+}
+
+struct Decompiler<'a> {
+    program: &'a [Op],
+    disassembly: Disassembly<'a>,
+    targets: BTreeSet<usize>,
+    lines: Vec<Line>,
+    gotos: BTreeSet<usize>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn new(program: &'a [Op]) -> Self {
+        let targets = program
+            .iter()
+            .enumerate()
+            .filter_map(|(addr, op)| jump_target(addr, op))
+            .filter_map(|addr| usize::try_from(addr).ok())
+            .collect();
+
+        Self {
+            program,
+            disassembly: Disassembly::new(program),
+            targets,
+            lines: Vec::new(),
+            gotos: BTreeSet::new(),
+        }
+    }
+
+    /// Decompiles the instructions from lo up to hi
+    fn block(&mut self, lo: usize, hi: usize, depth: usize, lp: Option<Loop>) {
+        let mut addr = lo;
+
+        while addr < hi {
+            addr = self.statement(addr, hi, depth, lp);
+        }
+    }
+
+    /// Decompiles the statement at an address, returning the address after it
+    fn statement(&mut self, addr: usize, hi: usize, depth: usize, lp: Option<Loop>) -> usize {
+        // Outermost loop with its head here, ignoring jumps which are never taken
+        let back_edge = (addr..hi).rev().find(|&j| {
+            matches!(
+                self.program[j],
+                Op::Instruction(Instruction::Jgz(..) | Instruction::Jnz(..))
+            ) && successors(j, &self.program[j])
+                .iter()
+                .any(|&(next, _)| next == Some(addr as i64))
+        });
+
+        if let Some(j) = back_edge {
+            let inner = Some(Loop {
+                head: addr,
+                exit: j + 1,
+            });
+
+            match self.jump_cond(j, addr) {
+                JumpCond::When(start, cond) => {
+                    self.line(addr, depth, "do {");
+                    self.block(addr, start, depth + 1, inner);
+                    self.line(j, depth, &format!("}} while {}", cond.text()));
+                }
+                _ => {
+                    self.line(addr, depth, "loop {");
+                    self.block(addr, j, depth + 1, inner);
+                    self.line(j, depth, "}");
+                }
+            }
+
+            return j + 1;
+        }
+
+        // Conditional jump, possibly with the condition computed from here
+        if let Some(j) = self.cond_run(addr, hi) {
+            return self.jump(addr, j, hi, depth, lp);
+        }
+
+        match self.program[addr] {
+            Op::Instruction(Instruction::Jgz(..) | Instruction::Jnz(..)) => {
+                self.jump(addr, addr, hi, depth, lp)
+            }
+            Op::Fused(fused) => {
+                let args = fused
+                    .to_string()
+                    .split_whitespace()
+                    .skip(1)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                self.line(addr, depth, &format!("{}({args})", fused.mnemonic()));
+
+                addr + fused.size()
+            }
+            Op::Instruction(instruction) => {
+                self.line(addr, depth, &statement(&instruction));
+
+                addr + 1
+            }
+        }
+    }
+
+    /// Decompiles the jump at j, whose condition is computed from start
+    fn jump(&mut self, start: usize, j: usize, hi: usize, depth: usize, lp: Option<Loop>) -> usize {
+        let target = jump_target(j, &self.program[j]);
+        let offset = match self.program[j] {
+            Op::Instruction(Instruction::Jgz(_, y) | Instruction::Jnz(_, y)) => y,
+            _ => unreachable!("Not a jump"),
+        };
+
+        let cond = match self.jump_cond(j, start) {
+            JumpCond::Never => return j + 1,
+            JumpCond::Always => {
+                let text = self.goto(target, offset, lp);
+                self.line(start, depth, &text);
+                return j + 1;
+            }
+            JumpCond::When(_, cond) => cond,
+        };
+
+        let in_block = |addr: i64| addr > j as i64 + 1 && addr <= hi as i64;
+
+        if let Some(t) = target.filter(|t| in_block(*t)) {
+            let t = t as usize;
+
+            // Jump over an unconditional jump
+            if t == j + 2 && !self.targets.contains(&(j + 1)) {
+                if let Some(u) = self.forward_jump(j + 1, hi) {
+                    self.line(start, depth, &format!("if {} {{", cond.text()));
+                    self.block(j + 2, u, depth + 1, lp);
+                    self.line(j, depth, "}");
+
+                    return u;
+                }
+            }
+
+            // If with an else block
+            if t > j + 2 && !self.targets.contains(&(t - 1)) {
+                if let Some(u) = self.forward_jump(t - 1, hi) {
+                    self.line(start, depth, &format!("if {} {{", cond.negate().text()));
+                    self.block(j + 1, t - 1, depth + 1, lp);
+                    self.line(t, depth, "} else {");
+                    self.block(t, u, depth + 1, lp);
+                    self.line(t, depth, "}");
+
+                    return u;
+                }
+            }
+
+            self.line(start, depth, &format!("if {} {{", cond.negate().text()));
+            self.block(j + 1, t, depth + 1, lp);
+            self.line(j, depth, "}");
+
+            return t;
+        }
+
+        if target == Some(j as i64 + 1) {
+            return j + 1;
+        }
+
+        let text = self.goto(target, offset, lp);
+
+        self.line(start, depth, &format!("if {} {{", cond.text()));
+        self.line(j, depth + 1, &text);
+        self.line(j, depth, "}");
+
+        j + 1
+    }
+
+    /// Returns the target of an unconditional forward jump which stays in the block
+    fn forward_jump(&self, addr: usize, hi: usize) -> Option<usize> {
+        let target = jump_target(addr, &self.program[addr])?;
+
+        match self.jump_cond(addr, addr) {
+            JumpCond::Always if target > addr as i64 + 1 && target <= hi as i64 => {
+                Some(target as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the statement for leaving the current block
+    fn goto(&mut self, target: Option<i64>, offset: RegImm, lp: Option<Loop>) -> String {
+        let Some(target) = target else {
+            return format!("jump {offset}");
+        };
+
+        let len = self.program.len() as i64;
+
+        match lp {
+            Some(lp) if target == lp.exit as i64 => "break".to_string(),
+            Some(lp) if target == lp.head as i64 => "continue".to_string(),
+            _ if target < 0 || target >= len => "exit".to_string(),
+            _ => {
+                self.gotos.insert(target as usize);
+                format!("goto {}", self.disassembly.target_name(target))
+            }
+        }
+    }
+
+    /// Returns the conditional jump at the end of a run of instructions computing its
+    /// condition starting at addr
+    fn cond_run(&self, addr: usize, hi: usize) -> Option<usize> {
+        let Op::Instruction(Instruction::Set(x, _)) = self.program[addr] else {
+            return None;
+        };
+
+        let j = (addr + 1..hi).find(|&k| !is_update(&self.program[k], x))?;
+
+        match self.jump_cond(j, addr) {
+            JumpCond::When(start, _) if start == addr => Some(j),
+            _ => None,
+        }
+    }
+
+    /// Returns the condition for the jump at j, folding in the instructions back to lo
+    /// which compute a temporary register for it
+    fn jump_cond(&self, j: usize, lo: usize) -> JumpCond {
+        let (x, op) = match self.program[j] {
+            Op::Instruction(Instruction::Jgz(x, _)) => (x, ">"),
+            Op::Instruction(Instruction::Jnz(x, _)) => (x, "!="),
+            _ => return JumpCond::Never,
+        };
+
+        let x = match x {
+            RegImm::Imm(x) if (op == ">" && x > 0) || (op == "!=" && x != 0) => {
+                return JumpCond::Always
+            }
+            RegImm::Imm(_) => return JumpCond::Never,
+            RegImm::Reg(x) => x,
+        };
+
+        let plain = JumpCond::When(
+            j,
+            Cond {
+                lhs: x.to_string(),
+                op,
+                rhs: "0".to_string(),
+            },
+        );
+
+        // Find the run of updates to the register starting with a set
+        let mut start = j;
+
+        while start > lo && is_update(&self.program[start - 1], x) {
+            start -= 1;
+        }
+
+        if start == lo || start == j {
+            return plain;
+        }
+
+        start -= 1;
+
+        let first = match self.program[start] {
+            Op::Instruction(Instruction::Set(reg, y)) if reg == x && y.reg() != Some(x) => y,
+            _ => return plain,
+        };
+
+        // Control must only enter at the start and the register must not be used later
+        if (start + 1..=j).any(|addr| self.targets.contains(&addr)) || !self.dead_after(x, j) {
+            return plain;
+        }
+
+        let updates = self.program[start + 1..j]
+            .iter()
+            .filter_map(Op::instruction)
+            .copied()
+            .collect::<Vec<_>>();
+
+        // Comparison written as -x + y
+        if let [Instruction::Mul(_, RegImm::Imm(-1)), Instruction::Add(_, y)] = updates[..] {
+            return JumpCond::When(
+                start,
+                Cond {
+                    lhs: y.to_string(),
+                    op,
+                    rhs: first.to_string(),
+                },
+            );
+        }
+
+        // Split off a final subtraction as the right hand side
+        let (updates, rhs) = match updates.split_last() {
+            Some((Instruction::Sub(_, y), rest)) => (rest, y.to_string()),
+            Some((Instruction::Add(_, RegImm::Imm(n)), rest)) => (rest, (-n).to_string()),
+            _ => (&updates[..], "0".to_string()),
+        };
+
+        let mut lhs = first.to_string();
+        let mut additive = false;
+
+        for update in updates {
+            match update {
+                Instruction::Add(_, RegImm::Imm(n)) | Instruction::Sub(_, RegImm::Imm(n))
+                    if (*n < 0) == matches!(update, Instruction::Add(..)) =>
+                {
+                    lhs = format!("{lhs} - {}", n.unsigned_abs());
+                    additive = true;
+                }
+                Instruction::Add(_, RegImm::Imm(n)) | Instruction::Sub(_, RegImm::Imm(n)) => {
+                    lhs = format!("{lhs} + {}", n.unsigned_abs());
+                    additive = true;
+                }
+                Instruction::Add(_, y) => {
+                    lhs = format!("{lhs} + {y}");
+                    additive = true;
+                }
+                Instruction::Sub(_, y) => {
+                    lhs = format!("{lhs} - {y}");
+                    additive = true;
+                }
+                Instruction::Mul(_, y) => {
+                    lhs = if additive {
+                        format!("({lhs}) * {y}")
+                    } else {
+                        format!("{lhs} * {y}")
+                    };
+                    additive = false;
+                }
+                _ => unreachable!("Not an update"),
+            }
+        }
+
+        JumpCond::When(start, Cond { lhs, op, rhs })
+    }
+
+    /// Returns true if the register is written before it is read on every path after
+    /// the instruction at addr
+    fn dead_after(&self, reg: Reg, addr: usize) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut work = vec![addr];
+
+        while let Some(addr) = work.pop() {
+            for (next, _) in successors(addr, &self.program[addr]) {
+                // Dynamic jumps could go anywhere
+                let Some(next) = next else {
+                    return false;
+                };
+
+                let Some(next) = usize::try_from(next)
+                    .ok()
+                    .filter(|next| *next < self.program.len())
+                else {
+                    continue;
+                };
+
+                if !visited.insert(next) {
+                    continue;
+                }
+
+                let op = &self.program[next];
+
+                if op.registers().contains(&reg) {
+                    // Only a set which doesn't read the register kills it
+                    match op {
+                        Op::Instruction(Instruction::Set(x, y))
+                            if *x == reg && y.reg() != Some(reg) => {}
+                        _ => return false,
+                    }
+                } else {
+                    work.push(next);
+                }
+            }
+        }
+
+        true
+    }
+
+    fn line(&mut self, addr: usize, depth: usize, text: &str) {
+        self.lines.push(Line {
+            addr,
+            depth,
+            text: text.to_string(),
+        });
+    }
+
+    /// Returns the lines with labels for goto targets
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let mut gotos = self.gotos.iter().peekable();
+
+        for line in &self.lines {
+            while let Some(target) = gotos.next_if(|target| **target <= line.addr) {
+                let label = self.disassembly.target_name(*target as i64);
+
+                out += &format!("{}{label}:\n", "    ".repeat(line.depth));
+            }
+
+            out += &format!("{}{}\n", "    ".repeat(line.depth), line.text);
+        }
+
+        out
+    }
+}
+
+/// Returns true if the operation updates the register from its current value
+fn is_update(op: &Op, x: Reg) -> bool {
+    match op {
+        Op::Instruction(
+            Instruction::Add(reg, y) | Instruction::Sub(reg, y) | Instruction::Mul(reg, y),
+        ) => *reg == x && y.reg() != Some(x),
+        _ => false,
+    }
+}
+
+/// Returns the statement for a simple instruction
+fn statement(instruction: &Instruction) -> String {
+    match instruction {
+        Instruction::Snd(x) => format!("snd({x})"),
+        Instruction::Rcv(x) => format!("rcv({x})"),
+        Instruction::Set(x, y) => format!("{x} = {y}"),
+        Instruction::Add(x, RegImm::Imm(n)) if *n < 0 => format!("{x} -= {}", n.unsigned_abs()),
+        Instruction::Add(x, y) => format!("{x} += {y}"),
+        Instruction::Sub(x, RegImm::Imm(n)) if *n < 0 => format!("{x} += {}", n.unsigned_abs()),
+        Instruction::Sub(x, y) => format!("{x} -= {y}"),
+        Instruction::Mul(x, y) => format!("{x} *= {y}"),
+        Instruction::Mod(x, y) => format!("{x} %= {y}"),
+        _ => instruction.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::parse_program;
+
+    use super::*;
+
+    #[test]
+    fn test_coprocessor() {
+        let program = parse_program(
+            "set b 93
+set c b
+jnz a 2
+jnz 1 5
+mul b 100
+sub b -100000
+set c b
+sub c -17000
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -17
+jnz 1 -23
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            decompile(&program.into_iter().map(Op::from).collect::<Vec<_>>()),
+            "b = 93
+c = b
+if a != 0 {
+    b *= 100
+    b += 100000
+    c = b
+    c += 17000
+}
+loop {
+    f = 1
+    d = 2
+    do {
+        e = 2
+        do {
+            if d * e == b {
+                f = 0
+            }
+            e += 1
+        } while e != b
+        d += 1
+    } while d != b
+    if f == 0 {
+        h += 1
+    }
+    if b == c {
+        break
+    }
+    b += 17
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_else_goto() {
+        let program = parse_program(
+            "rcv a
+jgz a 3
+snd 1
+jgz 1 2
+snd 2
+set g a
+add g -5
+jnz g -7
+jgz b c
+jnz b -2
+set g b
+mul g -1
+add g a
+jgz g -3
+",
+        )
+        .unwrap();
+
+        // The loop condition is a jump target so g isn't folded in to it
+        assert_eq!(
+            decompile(&program.into_iter().map(Op::from).collect::<Vec<_>>()),
+            "do {
+    rcv(a)
+    if a <= 0 {
+        snd(1)
+    } else {
+        snd(2)
+    }
+    g = a
+    g -= 5
+L4:
+} while g != 0
+if b > 0 {
+    jump c
+}
+if b != 0 {
+    goto L4
+}
+do {
+} while a > b
+"
+        );
+    }
+
+    #[test]
+    fn test_never_taken() {
+        // Constant jumps that are never taken don't make loops
+        for jump in ["jnz 0 -1", "jgz 0 -1", "jgz -3 -1"] {
+            let program = parse_program(&format!("set a 1\n{jump}\nsnd a")).unwrap();
+
+            assert_eq!(
+                decompile(&program.into_iter().map(Op::from).collect::<Vec<_>>()),
+                "a = 1\nsnd(a)\n",
+                "{jump}"
+            );
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    instruction::{Instruction, RegImm},
+    optimise::Op,
+};
+
+/// How control reaches a successor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Falls through or jumps unconditionally
+    Always,
+    /// The jump is taken
+    Taken,
+    /// The jump is not taken
+    NotTaken,
+}
+
+/// Destination of a control flow edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// Basic block index
+    Block(usize),
+    /// Address outside of the program, which ends it
+    Exit(i64),
+    /// Jump with a register offset
+    Dynamic,
+}
+
+/// Control flow edge out of a basic block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub target: Target,
+    pub flow: Flow,
+}
+
+/// Sequence of instructions only entered at the start and only left at the end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// Address of the first instruction
+    pub start: usize,
+    /// Address after the last instruction
+    pub end: usize,
+    pub edges: Vec<Edge>,
+}
+
+/// Program split into basic blocks with jump targets resolved to labels
+pub struct Disassembly<'a> {
+    program: &'a [Op],
+    blocks: Vec<Block>,
+    labels: BTreeMap<usize, String>,
+}
+
+/// Returns the address an operation jumps to if the offset is an immediate
+pub fn jump_target(addr: usize, op: &Op) -> Option<i64> {
+    match op {
+        Op::Instruction(
+            Instruction::Jgz(_, RegImm::Imm(offset)) | Instruction::Jnz(_, RegImm::Imm(offset)),
+        ) => Some((addr as i64).saturating_add(*offset)),
+        Op::Fused(fused) => Some((addr + fused.size()) as i64),
+        _ => None,
+    }
+}
+
+/// Returns the condition for a jump being taken or not, or None for other operations
+pub fn condition(op: &Op, taken: bool) -> Option<String> {
+    let cond = match (op, taken) {
+        (Op::Instruction(Instruction::Jgz(x, _)), true) => format!("{x} > 0"),
+        (Op::Instruction(Instruction::Jgz(x, _)), false) => format!("{x} <= 0"),
+        (Op::Instruction(Instruction::Jnz(x, _)), true) => format!("{x} != 0"),
+        (Op::Instruction(Instruction::Jnz(x, _)), false) => format!("{x} == 0"),
+        (Op::Fused(_), true) => "loop done".to_string(),
+        (Op::Fused(_), false) => "fallback".to_string(),
+        _ => None?,
+    };
+
+    Some(cond)
+}
+
+/// Returns the successors of an operation as address and flow, with None for a
+/// dynamic jump
+pub(crate) fn successors(addr: usize, op: &Op) -> Vec<(Option<i64>, Flow)> {
+    let next = Some(addr as i64 + 1);
+
+    match op {
+        Op::Instruction(instruction @ (Instruction::Jgz(x, _) | Instruction::Jnz(x, _))) => {
+            let target = jump_target(addr, op);
+
+            // Constant conditions always or never jump
+            let always = match (instruction, x) {
+                (Instruction::Jgz(..), RegImm::Imm(x)) => Some(*x > 0),
+                (Instruction::Jnz(..), RegImm::Imm(x)) => Some(*x != 0),
+                _ => None,
+            };
+
+            match always {
+                Some(true) => vec![(target, Flow::Always)],
+                Some(false) => vec![(next, Flow::Always)],
+                None => vec![(target, Flow::Taken), (next, Flow::NotTaken)],
+            }
+        }
+        Op::Fused(_) => vec![(jump_target(addr, op), Flow::Taken), (next, Flow::NotTaken)],
+        _ => vec![(next, Flow::Always)],
+    }
+}
+
+/// Returns true if the operation ends a basic block
+fn is_branch(op: &Op) -> bool {
+    matches!(
+        op,
+        Op::Instruction(Instruction::Jgz(..) | Instruction::Jnz(..)) | Op::Fused(_)
+    )
+}
+
+impl<'a> Disassembly<'a> {
+    /// Disassembles a program
+    pub fn new(program: &'a [Op]) -> Self {
+        let len = program.len();
+        let in_range = |addr: i64| usize::try_from(addr).ok().filter(|a| *a < len);
+
+        // Find jump targets, which get labels
+        let targets = program
+            .iter()
+            .enumerate()
+            .filter_map(|(addr, op)| jump_target(addr, op))
+            .filter_map(in_range)
+            .collect::<BTreeSet<_>>();
+
+        let labels = targets
+            .iter()
+            .enumerate()
+            .map(|(n, addr)| (*addr, format!("L{}", n + 1)))
+            .collect::<BTreeMap<_, _>>();
+
+        // Blocks start at the program start, jump targets and after branches
+        let mut leaders = targets.clone();
+
+        if len > 0 {
+            leaders.insert(0);
+        }
+
+        for (addr, op) in program.iter().enumerate() {
+            if is_branch(op) && addr + 1 < len {
+                leaders.insert(addr + 1);
+            }
+        }
+
+        let starts = leaders.into_iter().collect::<Vec<_>>();
+        let block_at = |addr: usize| starts.binary_search(&addr).ok();
+
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(len);
+                let last = end - 1;
+
+                let edges = successors(last, &program[last])
+                    .into_iter()
+                    .map(|(addr, flow)| {
+                        let target = match addr {
+                            None => Target::Dynamic,
+                            Some(addr) => match in_range(addr).and_then(block_at) {
+                                Some(block) => Target::Block(block),
+                                None => Target::Exit(addr),
+                            },
+                        };
+
+                        Edge { target, flow }
+                    })
+                    .collect();
+
+                Block { start, end, edges }
+            })
+            .collect();
+
+        Self {
+            program,
+            blocks,
+            labels,
+        }
+    }
+
+    /// Returns the program
+    pub fn program(&self) -> &'a [Op] {
+        self.program
+    }
+
+    /// Returns the basic blocks
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Returns the label for an address if it is a jump target
+    pub fn label(&self, addr: usize) -> Option<&str> {
+        self.labels.get(&addr).map(|l| l.as_str())
+    }
+
+    /// Returns the operation at an address with jump offsets replaced by labels
+    pub fn instruction(&self, addr: usize) -> String {
+        let op = &self.program[addr];
+
+        match (op, jump_target(addr, op)) {
+            (Op::Instruction(Instruction::Jgz(x, _) | Instruction::Jnz(x, _)), Some(target)) => {
+                format!("{} {x} {}", op.mnemonic(), self.target_name(target))
+            }
+            _ => op.to_string(),
+        }
+    }
+
+    /// Returns the name of a jump target, which is a label or exit for a target
+    /// outside of the program
+    pub fn target_name(&self, target: i64) -> String {
+        usize::try_from(target)
+            .ok()
+            .and_then(|addr| self.label(addr))
+            .map(|label| label.to_string())
+            .unwrap_or_else(|| format!("exit({target})"))
+    }
+
+    /// Returns the program listing with labels, split into basic blocks
+    pub fn listing(&self) -> String {
+        let mut out = String::new();
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+
+            for addr in block.start..block.end {
+                let label = self
+                    .label(addr)
+                    .map(|l| format!("{l}:"))
+                    .unwrap_or_default();
+
+                let line = format!("{addr:>4}  {label:<6}{}", self.instruction(addr));
+
+                match self.program[addr] {
+                    Op::Instruction(
+                        Instruction::Jgz(_, RegImm::Imm(offset))
+                        | Instruction::Jnz(_, RegImm::Imm(offset)),
+                    ) => writeln!(out, "{line:<30}; {offset:+}").unwrap(),
+                    _ => writeln!(out, "{line}").unwrap(),
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Returns the control flow graph in Graphviz DOT format
+    pub fn dot(&self) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "digraph program {{").unwrap();
+        writeln!(out, "    node [shape=box fontname=\"monospace\"];").unwrap();
+
+        let mut exits = BTreeSet::new();
+        let mut dynamic = false;
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            // Block node with left justified lines
+            let mut label = String::new();
+
+            if let Some(l) = self.label(block.start) {
+                write!(label, "{l}:\\l").unwrap();
+            }
+
+            for addr in block.start..block.end {
+                write!(label, "{addr:>4}: {}\\l", self.instruction(addr)).unwrap();
+            }
+
+            writeln!(out, "    b{i} [label=\"{label}\"];").unwrap();
+
+            // Edges
+            let last = &self.program[block.end - 1];
+
+            for edge in &block.edges {
+                let to = match edge.target {
+                    Target::Block(b) => format!("b{b}"),
+                    Target::Exit(addr) => {
+                        exits.insert(addr);
+                        format!("exit{}", addr.to_string().replace('-', "m"))
+                    }
+                    Target::Dynamic => {
+                        dynamic = true;
+                        "dynamic".to_string()
+                    }
+                };
+
+                let cond = match edge.flow {
+                    Flow::Always => None,
+                    Flow::Taken => condition(last, true),
+                    Flow::NotTaken => condition(last, false),
+                };
+
+                match cond {
+                    Some(cond) => writeln!(out, "    b{i} -> {to} [label=\"{cond}\"];").unwrap(),
+                    None => writeln!(out, "    b{i} -> {to};").unwrap(),
+                }
+            }
+        }
+
+        for addr in exits {
+            writeln!(
+                out,
+                "    exit{} [label=\"exit {addr}\" shape=ellipse];",
+                addr.to_string().replace('-', "m")
+            )
+            .unwrap();
+        }
+
+        if dynamic {
+            writeln!(out, "    dynamic [label=\"dynamic jump\" shape=ellipse];").unwrap();
+        }
+
+        writeln!(out, "}}").unwrap();
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::parse_program;
+
+    use super::*;
+
+    fn ops(listing: &str) -> Vec<Op> {
+        parse_program(listing)
+            .unwrap()
+            .into_iter()
+            .map(Op::from)
+            .collect()
+    }
+
+    const PROGRAM: &str = "set a 3
+set b 2
+sub b 1
+jnz b -1
+sub a 1
+jgz a -4
+jnz 1 3
+snd a
+jgz a c
+";
+
+    #[test]
+    fn test_blocks() {
+        let program = ops(PROGRAM);
+        let disassembly = Disassembly::new(&program);
+
+        let blocks = disassembly
+            .blocks()
+            .iter()
+            .map(|b| (b.start, b.end))
+            .collect::<Vec<_>>();
+
+        assert_eq!(blocks, vec![(0, 1), (1, 2), (2, 4), (4, 6), (6, 7), (7, 9)]);
+
+        assert_eq!(
+            disassembly.blocks()[2].edges,
+            vec![
+                Edge {
+                    target: Target::Block(2),
+                    flow: Flow::Taken
+                },
+                Edge {
+                    target: Target::Block(3),
+                    flow: Flow::NotTaken
+                }
+            ]
+        );
+
+        // Constant jump out of the program
+        assert_eq!(
+            disassembly.blocks()[4].edges,
+            vec![Edge {
+                target: Target::Exit(9),
+                flow: Flow::Always
+            }]
+        );
+
+        // Jump with a register offset
+        assert_eq!(disassembly.blocks()[5].edges[0].target, Target::Dynamic);
+    }
+
+    #[test]
+    fn test_listing() {
+        let program = ops(PROGRAM);
+        let disassembly = Disassembly::new(&program);
+
+        assert_eq!(
+            disassembly.listing(),
+            "   0        set a 3
+
+   1  L1:   set b 2
+
+   2  L2:   sub b 1
+   3        jnz b L2          ; -1
+
+   4        sub a 1
+   5        jgz a L1          ; -4
+
+   6        jnz 1 exit(9)     ; +3
+
+   7        snd a
+   8        jgz a c
+"
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        let program = ops("set a 2\nsub a 1\njnz a -1\njgz a c");
+        let disassembly = Disassembly::new(&program);
+
+        assert_eq!(
+            disassembly.dot(),
+            r#"digraph program {
+    node [shape=box fontname="monospace"];
+    b0 [label="   0: set a 2\l"];
+    b0 -> b1;
+    b1 [label="L1:\l   1: sub a 1\l   2: jnz a L1\l"];
+    b1 -> b1 [label="a != 0"];
+    b1 -> b2 [label="a == 0"];
+    b2 [label="   3: jgz a c\l"];
+    b2 -> dynamic [label="a > 0"];
+    b2 -> exit4 [label="a <= 0"];
+    exit4 [label="exit 4" shape=ellipse];
+    dynamic [label="dynamic jump" shape=ellipse];
+}
+"#
+        );
+    }
+}
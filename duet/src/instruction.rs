@@ -0,0 +1,259 @@
+use std::{fmt, str::FromStr};
+
+/// Number of registers (a to z)
+pub const REGISTERS: usize = 26;
+
+/// Assembly instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Snd(RegImm),
+    Set(Reg, RegImm),
+    Add(Reg, RegImm),
+    Sub(Reg, RegImm),
+    Mul(Reg, RegImm),
+    Mod(Reg, RegImm),
+    Rcv(Reg),
+    Jgz(RegImm, RegImm),
+    Jnz(RegImm, RegImm),
+}
+
+impl Instruction {
+    /// Returns the instruction mnemonic
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Snd(_) => "snd",
+            Instruction::Set(_, _) => "set",
+            Instruction::Add(_, _) => "add",
+            Instruction::Sub(_, _) => "sub",
+            Instruction::Mul(_, _) => "mul",
+            Instruction::Mod(_, _) => "mod",
+            Instruction::Rcv(_) => "rcv",
+            Instruction::Jgz(_, _) => "jgz",
+            Instruction::Jnz(_, _) => "jnz",
+        }
+    }
+
+    /// Returns the registers the instruction refers to
+    pub fn registers(&self) -> Vec<Reg> {
+        match self {
+            Instruction::Snd(x) => x.reg().into_iter().collect(),
+            Instruction::Rcv(x) => vec![*x],
+            Instruction::Set(x, y)
+            | Instruction::Add(x, y)
+            | Instruction::Sub(x, y)
+            | Instruction::Mul(x, y)
+            | Instruction::Mod(x, y) => [Some(*x), y.reg()].into_iter().flatten().collect(),
+            Instruction::Jgz(x, y) | Instruction::Jnz(x, y) => {
+                [x.reg(), y.reg()].into_iter().flatten().collect()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = self.mnemonic();
+
+        match self {
+            Instruction::Snd(x) => write!(f, "{mnemonic} {x}"),
+            Instruction::Rcv(x) => write!(f, "{mnemonic} {x}"),
+            Instruction::Set(x, y)
+            | Instruction::Add(x, y)
+            | Instruction::Sub(x, y)
+            | Instruction::Mul(x, y)
+            | Instruction::Mod(x, y) => write!(f, "{mnemonic} {x} {y}"),
+            Instruction::Jgz(x, y) | Instruction::Jnz(x, y) => write!(f, "{mnemonic} {x} {y}"),
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut iter = line.split_whitespace();
+
+        let opcode = iter.next().ok_or("Opcode not found")?;
+
+        let mut reg = || {
+            iter.next()
+                .ok_or_else(|| format!("Expecting register in '{line}'"))
+                .and_then(|term| term.parse::<Reg>())
+        };
+
+        let instruction = match opcode {
+            "snd" => Instruction::Snd(RegImm::parse(iter.next(), line)?),
+            "set" => Instruction::Set(reg()?, RegImm::parse(iter.next(), line)?),
+            "add" => Instruction::Add(reg()?, RegImm::parse(iter.next(), line)?),
+            "sub" => Instruction::Sub(reg()?, RegImm::parse(iter.next(), line)?),
+            "mul" => Instruction::Mul(reg()?, RegImm::parse(iter.next(), line)?),
+            "mod" => Instruction::Mod(reg()?, RegImm::parse(iter.next(), line)?),
+            "rcv" => Instruction::Rcv(reg()?),
+            "jgz" => Instruction::Jgz(
+                RegImm::parse(iter.next(), line)?,
+                RegImm::parse(iter.next(), line)?,
+            ),
+            "jnz" => Instruction::Jnz(
+                RegImm::parse(iter.next(), line)?,
+                RegImm::parse(iter.next(), line)?,
+            ),
+            _ => Err(format!("Invalid opcode {opcode}"))?,
+        };
+
+        if iter.next().is_some() {
+            Err(format!("Too many operands in '{line}'"))?
+        }
+
+        Ok(instruction)
+    }
+}
+
+/// Register (a to z)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Reg(u8);
+
+impl Reg {
+    /// Creates a register from its name
+    pub fn new(name: char) -> Option<Self> {
+        if name.is_ascii_lowercase() {
+            Some(Self(name as u8 - b'a'))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the register index
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    /// Returns the register name
+    pub fn name(&self) -> char {
+        (b'a' + self.0) as char
+    }
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Reg {
+    type Err = String;
+
+    fn from_str(term: &str) -> Result<Self, Self::Err> {
+        let mut chars = term.chars();
+
+        match (chars.next().and_then(Reg::new), chars.next()) {
+            (Some(reg), None) => Ok(reg),
+            _ => Err(format!("Invalid register {term}")),
+        }
+    }
+}
+
+/// Parses a register setting given on the command line as reg=value
+pub fn parse_setting(arg: &str) -> Result<(Reg, i64), String> {
+    let (reg, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid register setting {arg}"))?;
+
+    let value = value
+        .parse::<i64>()
+        .map_err(|_| format!("Invalid register value in {arg}"))?;
+
+    Ok((reg.parse()?, value))
+}
+
+/// Register or immediate operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegImm {
+    Reg(Reg),
+    Imm(i64),
+}
+
+impl RegImm {
+    /// Returns the register if the operand is a register
+    pub fn reg(&self) -> Option<Reg> {
+        match self {
+            RegImm::Reg(reg) => Some(*reg),
+            RegImm::Imm(_) => None,
+        }
+    }
+
+    fn parse(term: Option<&str>, line: &str) -> Result<Self, String> {
+        let term = term.ok_or_else(|| format!("Expecting register or immediate in '{line}'"))?;
+
+        match term.parse::<Reg>() {
+            Ok(reg) => Ok(RegImm::Reg(reg)),
+            Err(_) => term
+                .parse::<i64>()
+                .map(RegImm::Imm)
+                .map_err(|_| format!("Invalid register or immediate {term}")),
+        }
+    }
+}
+
+impl fmt::Display for RegImm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegImm::Reg(reg) => write!(f, "{reg}"),
+            RegImm::Imm(imm) => write!(f, "{imm}"),
+        }
+    }
+}
+
+/// Parses a program listing
+pub fn parse_program(listing: &str) -> Result<Vec<Instruction>, String> {
+    listing
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.parse::<Instruction>())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let listing =
+            "snd a\nset b -3\nadd c b\nsub d 7\nmul e f\nmod g 5\nrcv h\njgz 1 -2\njnz z 3\n";
+
+        let program = parse_program(listing).unwrap();
+
+        assert_eq!(program.len(), 9);
+        assert_eq!(
+            program[1],
+            Instruction::Set(Reg::new('b').unwrap(), RegImm::Imm(-3))
+        );
+        assert_eq!(
+            program[7],
+            Instruction::Jgz(RegImm::Imm(1), RegImm::Imm(-2))
+        );
+
+        // Display gives back the listing
+        let out = program.iter().map(|i| format!("{i}\n")).collect::<String>();
+
+        assert_eq!(out, listing);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("nop a".parse::<Instruction>().is_err());
+        assert!("set 1 2".parse::<Instruction>().is_err());
+        assert!("set A 2".parse::<Instruction>().is_err());
+        assert!("add a".parse::<Instruction>().is_err());
+        assert!("add a 1 2".parse::<Instruction>().is_err());
+        assert!("jnz a x1".parse::<Instruction>().is_err());
+    }
+
+    #[test]
+    fn test_setting() {
+        assert_eq!(parse_setting("a=-5"), Ok((Reg::new('a').unwrap(), -5)));
+        assert!(parse_setting("a").is_err());
+        assert!(parse_setting("ab=1").is_err());
+        assert!(parse_setting("a=x").is_err());
+    }
+}
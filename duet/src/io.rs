@@ -0,0 +1,45 @@
+use std::collections::VecDeque;
+
+use crate::vm::{Io, Rcv};
+
+/// Io which collects snd values and takes rcv values from a queue, blocking when
+/// it is empty
+#[derive(Debug, Default)]
+pub struct Queue {
+    pub input: VecDeque<i64>,
+    pub output: Vec<i64>,
+}
+
+impl Io for Queue {
+    fn snd(&mut self, value: i64) {
+        self.output.push(value);
+    }
+
+    fn rcv(&mut self, _current: i64) -> Rcv {
+        match self.input.pop_front() {
+            Some(value) => Rcv::Store(value),
+            None => Rcv::Block,
+        }
+    }
+}
+
+/// Io where snd plays a sound and rcv recovers the last sound played if the register
+/// is non-zero, stopping the program
+#[derive(Debug, Default)]
+pub struct Sound {
+    pub last_freq: i64,
+}
+
+impl Io for Sound {
+    fn snd(&mut self, value: i64) {
+        self.last_freq = value;
+    }
+
+    fn rcv(&mut self, current: i64) -> Rcv {
+        if current != 0 {
+            Rcv::Halt
+        } else {
+            Rcv::Skip
+        }
+    }
+}
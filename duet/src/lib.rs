@@ -0,0 +1,10 @@
+pub mod compile;
+pub mod debugger;
+pub mod decompile;
+pub mod disasm;
+pub mod instruction;
+pub mod io;
+pub mod optimise;
+pub mod profile;
+pub mod scheduler;
+pub mod vm;
@@ -0,0 +1,583 @@
+use std::fmt;
+
+use crate::{
+    instruction::{
+        Instruction::{self, Add, Jnz, Mul, Set, Sub},
+        Reg,
+        RegImm::{self, Imm},
+        REGISTERS,
+    },
+    vm::{self, Io, Machine, Stop},
+};
+
+/// Operation of an optimised program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Instruction left as it was
+    Instruction(Instruction),
+    /// First instruction of a loop replaced by a fused operation
+    Fused(Fused),
+}
+
+impl Op {
+    /// Returns the mnemonic of the operation
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Op::Instruction(instruction) => instruction.mnemonic(),
+            Op::Fused(fused) => fused.mnemonic(),
+        }
+    }
+
+    /// Returns the registers the operation refers to
+    pub fn registers(&self) -> Vec<Reg> {
+        match self {
+            Op::Instruction(instruction) => instruction.registers(),
+            Op::Fused(fused) => fused.registers(),
+        }
+    }
+
+    /// Returns the instruction if the operation wasn't replaced
+    pub fn instruction(&self) -> Option<&Instruction> {
+        match self {
+            Op::Instruction(instruction) => Some(instruction),
+            Op::Fused(_) => None,
+        }
+    }
+}
+
+impl From<Instruction> for Op {
+    fn from(instruction: Instruction) -> Self {
+        Op::Instruction(instruction)
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Instruction(instruction) => write!(f, "{instruction}"),
+            Op::Fused(fused) => write!(f, "{fused}"),
+        }
+    }
+}
+
+impl vm::Op for Op {
+    fn execute<I: Io + ?Sized>(
+        self,
+        registers: &mut [i64; REGISTERS],
+        ip: i64,
+        io: &mut I,
+    ) -> Result<i64, Stop> {
+        match self {
+            Op::Instruction(instruction) => vm::Op::execute(instruction, registers, ip, io),
+            Op::Fused(fused) => match fused.execute(registers) {
+                Some(size) => Ok(ip + size as i64),
+                None => vm::Op::execute(fused.fallback(), registers, ip, io),
+            },
+        }
+    }
+}
+
+/// Operation replacing a recognised loop.
+///
+/// A fused operation replaces the first instruction of the loop and the rest of the loop
+/// is left in place, so jump targets are unchanged. When the loop's preconditions don't
+/// hold the fused operation executes the instruction it replaced instead. Registers and
+/// control flow are the same as the original loop; only the step count differs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fused {
+    /// Add-multiply loop
+    /// ```text
+    /// add x y
+    /// sub c 1
+    /// jnz c -2
+    /// ```
+    /// Adds y * c to x and clears c
+    AddMul { x: Reg, y: RegImm, c: Reg },
+    /// Multiply-compare loop
+    /// ```text
+    /// set g d
+    /// mul g e
+    /// sub g b
+    /// jnz g 2
+    /// set f 0
+    /// sub e -1
+    /// set g e
+    /// sub g b
+    /// jnz g -8
+    /// ```
+    /// Clears f if d * e == b for any e up to b. Leaves e = b and g = 0
+    MulTest {
+        d: Reg,
+        e: Reg,
+        b: Reg,
+        f: Reg,
+        g: Reg,
+    },
+    /// Multiply-compare loop nested in an increment loop
+    /// ```text
+    /// set e 2
+    /// <multiply-compare loop>
+    /// sub d -1
+    /// set g d
+    /// sub g b
+    /// jnz g -13
+    /// ```
+    /// Clears f if b has a factor pair d * e for any d up to b and e from 2 up to b.
+    /// Leaves d = b, e = b and g = 0
+    FactorTest {
+        d: Reg,
+        e: Reg,
+        b: Reg,
+        f: Reg,
+        g: Reg,
+    },
+}
+
+impl Fused {
+    /// Returns the mnemonic of the fused operation
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Fused::AddMul { .. } => "addmul",
+            Fused::MulTest { .. } => "multest",
+            Fused::FactorTest { .. } => "factortest",
+        }
+    }
+
+    /// Returns the number of instructions in the loop
+    pub fn size(&self) -> usize {
+        match self {
+            Fused::AddMul { .. } => 3,
+            Fused::MulTest { .. } => 9,
+            Fused::FactorTest { .. } => 14,
+        }
+    }
+
+    /// Returns the instruction replaced by the fused operation
+    pub fn fallback(&self) -> Instruction {
+        match *self {
+            Fused::AddMul { x, y, .. } => Add(x, y),
+            Fused::MulTest { d, g, .. } => Set(g, RegImm::Reg(d)),
+            Fused::FactorTest { e, .. } => Set(e, Imm(2)),
+        }
+    }
+
+    /// Returns the registers the fused operation refers to
+    pub fn registers(&self) -> Vec<Reg> {
+        match *self {
+            Fused::AddMul { x, y, c } => {
+                [Some(x), y.reg(), Some(c)].into_iter().flatten().collect()
+            }
+            Fused::MulTest { d, e, b, f, g } | Fused::FactorTest { d, e, b, f, g } => {
+                vec![d, e, b, f, g]
+            }
+        }
+    }
+
+    /// Executes the whole loop if its preconditions hold, returning the number of
+    /// instructions to skip. Returns None if the fallback instruction should be executed
+    pub fn execute(&self, registers: &mut [i64; REGISTERS]) -> Option<usize> {
+        let reg = |r: Reg| registers[r.index()];
+
+        match *self {
+            Fused::AddMul { x, y, c } => {
+                let count = reg(c);
+
+                if count <= 0 {
+                    return None;
+                }
+
+                let y = match y {
+                    RegImm::Reg(y) => reg(y),
+                    Imm(y) => y,
+                };
+
+                let value = y.checked_mul(count)?.checked_add(reg(x))?;
+
+                registers[x.index()] = value;
+                registers[c.index()] = 0;
+            }
+            Fused::MulTest { d, e, b, f, g } => {
+                let (d_val, e_val, b_val) = (reg(d), reg(e), reg(b));
+
+                // Otherwise e never reaches b
+                if e_val >= b_val {
+                    return None;
+                }
+
+                // d * e - b must not overflow (it's linear in e so checking the ends is enough)
+                d_val.checked_mul(e_val)?.checked_sub(b_val)?;
+                d_val.checked_mul(b_val - 1)?.checked_sub(b_val)?;
+
+                // Neither must the first e + 1 - b (later values are nearer zero)
+                e_val.checked_add(1)?.checked_sub(b_val)?;
+
+                let found = if d_val == 0 {
+                    b_val == 0
+                } else {
+                    b_val.checked_rem(d_val) == Some(0)
+                        && b_val
+                            .checked_div(d_val)
+                            .is_some_and(|q| (e_val..b_val).contains(&q))
+                };
+
+                if found {
+                    registers[f.index()] = 0;
+                }
+
+                registers[e.index()] = b_val;
+                registers[g.index()] = 0;
+            }
+            Fused::FactorTest { d, e, b, f, g } => {
+                let (d_val, b_val) = (reg(d), reg(b));
+
+                // Otherwise the inner loop or the outer loop never finishes
+                if b_val <= 2 || d_val >= b_val {
+                    return None;
+                }
+
+                // d * e - b must not overflow at the corners of the ranges
+                for (d_end, e_end) in [(d_val, 2), (d_val, b_val - 1), (b_val - 1, 2)] {
+                    d_end.checked_mul(e_end)?.checked_sub(b_val)?;
+                }
+                (b_val - 1).checked_mul(b_val - 1)?;
+
+                // Only factors d >= 2 can pair with e >= 2, and e < b holds for
+                // any pair, so look for a factor pair with the larger factor >= d
+                let found = (2..)
+                    .take_while(|p: &i64| p * p <= b_val)
+                    .any(|p| b_val % p == 0 && b_val / p >= d_val);
+
+                if found {
+                    registers[f.index()] = 0;
+                }
+
+                registers[d.index()] = b_val;
+                registers[e.index()] = b_val;
+                registers[g.index()] = 0;
+            }
+        }
+
+        Some(self.size())
+    }
+}
+
+impl fmt::Display for Fused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = self.mnemonic();
+
+        match self {
+            Fused::AddMul { x, y, c } => write!(f, "{mnemonic} {x} {y} {c}"),
+            _ => {
+                let regs = self
+                    .registers()
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<_>>();
+
+                write!(f, "{mnemonic} {}", regs.join(" "))
+            }
+        }
+    }
+}
+
+/// Program with recognised loops replaced by fused operations. Each instruction becomes
+/// one operation so addresses are unchanged
+pub struct Optimised {
+    program: Vec<Instruction>,
+    ops: Vec<Op>,
+}
+
+impl Optimised {
+    /// Returns the program the operations were optimised from
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    /// Returns the operations
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+}
+
+/// Virtual machine running an optimised program
+pub type OptimisedVm<'a> = Machine<'a, Op>;
+
+impl<'a> OptimisedVm<'a> {
+    /// Creates a new virtual machine with all registers zeroed
+    pub fn new(optimised: &'a Optimised) -> Self {
+        Self::with_ops(&optimised.program, &optimised.ops)
+    }
+}
+
+/// Replaces recognised loops in a program with fused operations
+pub fn optimise(program: &[Instruction]) -> Optimised {
+    let mut ops = program.iter().map(|&i| Op::from(i)).collect::<Vec<_>>();
+
+    // Innermost loops first
+    for matcher in [add_mul, mul_test, factor_test] {
+        for i in 0..ops.len() {
+            if let Some(fused) = matcher(&ops[i..]) {
+                ops[i] = Op::Fused(fused);
+            }
+        }
+    }
+
+    Optimised {
+        program: program.to_vec(),
+        ops,
+    }
+}
+
+fn add_mul(window: &[Op]) -> Option<Fused> {
+    use Op::Instruction as I;
+    use RegImm::Reg as R;
+
+    match *window {
+        [I(Add(x, y)), I(Sub(c, Imm(1)) | Add(c, Imm(-1))), I(Jnz(R(c2), Imm(-2))), ..]
+            if c == c2 && x != c && y.reg() != Some(x) && y.reg() != Some(c) =>
+        {
+            Some(Fused::AddMul { x, y, c })
+        }
+        _ => None,
+    }
+}
+
+fn mul_test(window: &[Op]) -> Option<Fused> {
+    use Op::Instruction as I;
+    use RegImm::Reg as R;
+
+    match *window {
+        [I(Set(g, R(d))), I(Mul(g2, R(e))), I(Sub(g3, R(b))), I(Jnz(R(g4), Imm(2))), I(Set(f, Imm(0))), I(Sub(e2, Imm(-1)) | Add(e2, Imm(1))), I(Set(g5, R(e3))), I(Sub(g6, R(b2))), I(Jnz(R(g7), Imm(-8))), ..]
+            if [g2, g3, g4, g5, g6, g7].iter().all(|r| *r == g)
+                && e2 == e
+                && e3 == e
+                && b2 == b
+                && distinct(&[d, e, b, f, g]) =>
+        {
+            Some(Fused::MulTest { d, e, b, f, g })
+        }
+        _ => None,
+    }
+}
+
+fn factor_test(window: &[Op]) -> Option<Fused> {
+    use Op::Instruction as I;
+    use RegImm::Reg as R;
+
+    match *window {
+        [I(Set(e, Imm(2))), Op::Fused(Fused::MulTest { d, e: e2, b, f, g }), _, _, _, _, _, _, _, _, I(Sub(d2, Imm(-1)) | Add(d2, Imm(1))), I(Set(g2, R(d3))), I(Sub(g3, R(b2))), I(Jnz(R(g4), Imm(-13))), ..]
+            if e2 == e && d2 == d && d3 == d && b2 == b && [g2, g3, g4].iter().all(|r| *r == g) =>
+        {
+            Some(Fused::FactorTest { d, e, b, f, g })
+        }
+        _ => None,
+    }
+}
+
+fn distinct(regs: &[Reg]) -> bool {
+    regs.iter()
+        .enumerate()
+        .all(|(i, r)| !regs[i + 1..].contains(r))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        instruction::parse_program,
+        vm::{Halt, NoIo, Vm},
+    };
+
+    use super::*;
+
+    /// Counts composites from b to c in steps of 17
+    const PRIMES: &str = "set b 100
+set c b
+sub c -170
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -17
+jnz 1 -23
+";
+
+    fn run<O: vm::Op>(mut vm: Machine<O>, setup: &[(char, i64)]) -> (Stop, [i64; REGISTERS], u64) {
+        for (reg, value) in setup {
+            vm.set_reg(Reg::new(*reg).unwrap(), *value);
+        }
+
+        let stop = vm.run(&mut NoIo);
+
+        (stop, *vm.registers(), vm.steps())
+    }
+
+    #[test]
+    fn test_recognise() {
+        let program = parse_program(PRIMES).unwrap();
+        let optimised = optimise(&program);
+
+        assert_eq!(optimised.ops().len(), program.len());
+        assert_eq!(optimised.program(), program);
+
+        let fused = optimised
+            .ops()
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| matches!(op, Op::Fused(_)))
+            .map(|(addr, op)| format!("{addr}: {op}"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            fused,
+            vec!["5: factortest d e b f g", "6: multest d e b f g"]
+        );
+    }
+
+    #[test]
+    fn test_same_result() {
+        let program = parse_program(PRIMES).unwrap();
+        let optimised = optimise(&program);
+
+        let (stop, registers, steps) = run(Vm::new(&program), &[]);
+        let (opt_stop, opt_registers, opt_steps) = run(OptimisedVm::new(&optimised), &[]);
+
+        assert_eq!(opt_stop, stop);
+        assert_eq!(opt_registers, registers);
+        assert_eq!(registers[7], 10);
+        assert!(opt_steps * 1000 < steps);
+    }
+
+    #[test]
+    fn test_add_mul() {
+        let program = parse_program(
+            "add a 3
+sub c 1
+jnz c -2
+add b a
+add d -1
+jnz d -2
+",
+        )
+        .unwrap();
+        let optimised = optimise(&program);
+
+        assert_eq!(optimised.ops()[0].to_string(), "addmul a 3 c");
+        assert_eq!(optimised.ops()[3].to_string(), "addmul b a d");
+
+        // Preconditions hold
+        let setup = [('a', 1), ('c', 10), ('d', 4)];
+
+        let (_, registers, _) = run(Vm::new(&program), &setup);
+        let (_, opt_registers, opt_steps) = run(OptimisedVm::new(&optimised), &setup);
+
+        assert_eq!(opt_registers, registers);
+        assert_eq!(opt_registers[1], 4 * 31);
+        assert_eq!(opt_steps, 2);
+
+        // Falls back when the count doesn't start positive (c wraps round in the original)
+        let program = parse_program("add a 3\nsub c 1\njnz c -2").unwrap();
+        let optimised = optimise(&program);
+
+        let mut vm = OptimisedVm::new(&optimised);
+
+        for _ in 0..4 {
+            vm.step(&mut NoIo);
+        }
+
+        // Executed add a 3 twice round the loop
+        assert_eq!(vm.reg(Reg::new('a').unwrap()), 6);
+        assert_eq!(vm.reg(Reg::new('c').unwrap()), -1);
+        assert_eq!(vm.ip(), 1);
+    }
+
+    #[test]
+    fn test_mul_test() {
+        let program = parse_program(PRIMES).unwrap();
+        let inner = &program[6..15];
+        let optimised = optimise(inner);
+
+        // Run just the inner loop from various starting states
+        for (d, e, b) in [
+            (2, 2, 10),
+            (3, 2, 10),
+            (5, 3, 10),
+            (5, 2, 9),
+            (-2, -5, 4),
+            (0, -1, 0),
+        ] {
+            let setup = [('d', d), ('e', e), ('b', b), ('f', 1), ('h', 99)];
+
+            let mut vm = Vm::new(inner);
+            let mut opt_vm = OptimisedVm::new(&optimised);
+
+            for (reg, value) in setup {
+                vm.set_reg(Reg::new(reg).unwrap(), value);
+                opt_vm.set_reg(Reg::new(reg).unwrap(), value);
+            }
+
+            vm.run(&mut NoIo);
+            opt_vm.run(&mut NoIo);
+
+            assert_eq!(opt_vm.registers(), vm.registers(), "d={d} e={e} b={b}");
+            assert_eq!(opt_vm.steps(), 1);
+        }
+
+        // Falls back when stepping e overflows, halting like the original
+        for (d, e, b) in [(0, i64::MIN, 10), (-1, i64::MIN + 1, 10)] {
+            let setup = [('d', d), ('e', e), ('b', b), ('f', 1)];
+
+            let (stop, registers, steps) = run(Vm::new(inner), &setup);
+            let (opt_stop, opt_registers, opt_steps) = run(OptimisedVm::new(&optimised), &setup);
+
+            assert!(
+                matches!(stop, Stop::Halted(Halt::Overflow(_))),
+                "d={d} e={e} b={b}"
+            );
+            assert_eq!(opt_stop, stop, "d={d} e={e} b={b}");
+            assert_eq!(opt_registers, registers, "d={d} e={e} b={b}");
+            assert_eq!(opt_steps, steps, "d={d} e={e} b={b}");
+        }
+    }
+
+    /// Returns true if the optimiser left every instruction as it was
+    fn unchanged(program: &[Instruction]) -> bool {
+        optimise(program)
+            .ops()
+            .iter()
+            .zip(program)
+            .all(|(op, instruction)| op.instruction() == Some(instruction))
+    }
+
+    #[test]
+    fn test_no_match() {
+        // Loop counter is also the addend
+        let program = parse_program("add a c\nsub c 1\njnz c -2").unwrap();
+        assert!(unchanged(&program));
+
+        // Wrong jump offset
+        let program = parse_program("add a 1\nsub c 1\njnz c -3").unwrap();
+        assert!(unchanged(&program));
+
+        // Truncated multiply-compare loop
+        let program = parse_program(PRIMES).unwrap();
+        assert!(unchanged(&program[6..14]));
+    }
+}
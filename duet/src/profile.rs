@@ -0,0 +1,266 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    instruction::Instruction,
+    vm::{Io, Machine, Op, Step, Stop},
+};
+
+/// Execution profile of a program
+pub struct Profile {
+    counts: Vec<u64>,
+    edges: BTreeMap<(usize, i64), u64>,
+    total: u64,
+}
+
+/// Loop found from a taken backward jump
+#[derive(Debug, PartialEq, Eq)]
+pub struct Loop {
+    /// First instruction in the loop (the jump target)
+    pub start: usize,
+    /// Last instruction in the loop (the backward jump)
+    pub end: usize,
+    /// Number of times the backward jump was taken
+    pub iterations: u64,
+    /// Number of instructions executed inside the loop
+    pub executed: u64,
+    /// Number of loops this loop is nested in
+    pub depth: usize,
+}
+
+impl Profile {
+    /// Creates an empty profile for a program of a given length
+    pub fn new(len: usize) -> Self {
+        Self {
+            counts: vec![0; len],
+            edges: BTreeMap::new(),
+            total: 0,
+        }
+    }
+
+    /// Runs a program collecting a profile, stopping after an optional number of steps.
+    /// Returns the profile and the reason the program stopped, if it did
+    pub fn run<O: Op, I: Io + ?Sized>(
+        vm: &mut Machine<O>,
+        io: &mut I,
+        max_steps: Option<u64>,
+    ) -> (Self, Option<Stop>) {
+        let mut profile = Self::new(vm.program().len());
+
+        let stop = loop {
+            if max_steps.is_some_and(|max| profile.total >= max) {
+                break None;
+            }
+
+            match vm.step(io) {
+                Step::Executed(addr) => profile.record(addr, vm.ip()),
+                Step::Blocked => break Some(Stop::Blocked),
+                Step::Halted(halt) => break Some(Stop::Halted(halt)),
+            }
+        };
+
+        (profile, stop)
+    }
+
+    /// Records the execution of an instruction and the address executed next
+    pub fn record(&mut self, addr: usize, next: i64) {
+        self.counts[addr] += 1;
+        self.total += 1;
+
+        if next != addr as i64 + 1 {
+            *self.edges.entry((addr, next)).or_insert(0) += 1;
+        }
+    }
+
+    /// Returns the execution count of each instruction
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Returns the number of times each jump was taken, keyed by source and target address
+    pub fn edges(&self) -> &BTreeMap<(usize, i64), u64> {
+        &self.edges
+    }
+
+    /// Returns the total number of instructions executed
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the loops found from taken backward jumps, outermost first
+    pub fn loops(&self) -> Vec<Loop> {
+        let mut loops = self
+            .edges
+            .iter()
+            .filter(|((from, to), _)| *to >= 0 && *to <= *from as i64)
+            .map(|((from, to), iterations)| {
+                let (start, end) = (*to as usize, *from);
+
+                Loop {
+                    start,
+                    end,
+                    iterations: *iterations,
+                    executed: self.counts[start..=end].iter().sum(),
+                    depth: 0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Sort by start then by size, largest first
+        loops.sort_by_key(|l| (l.start, usize::MAX - l.end));
+
+        // Calculate nesting depth
+        for i in 0..loops.len() {
+            loops[i].depth = loops[..i]
+                .iter()
+                .filter(|outer| outer.start <= loops[i].start && loops[i].end <= outer.end)
+                .count();
+        }
+
+        loops
+    }
+
+    /// Returns the program listing annotated with execution counts, loops and jumps taken
+    pub fn listing(&self, program: &[Instruction]) -> String {
+        let loops = self.loops();
+        let width = loops.iter().map(|l| l.depth + 1).max().unwrap_or(0);
+
+        let mut out = String::new();
+
+        writeln!(out, "addr        count       %  instruction").unwrap();
+
+        for (addr, instruction) in program.iter().enumerate() {
+            let count = self.counts[addr];
+
+            // Draw loop brackets
+            let mut brackets = vec![' '; width];
+
+            for l in &loops {
+                if l.start <= addr && addr <= l.end {
+                    brackets[l.depth] = match (addr == l.start, addr == l.end) {
+                        (true, true) => '<',
+                        (true, false) => '/',
+                        (false, true) => '\\',
+                        (false, false) => '|',
+                    };
+                }
+            }
+
+            let mut line = format!(
+                "{addr:>4} {count:>12} {:>6.2}%  {}{instruction}",
+                self.percent(count),
+                brackets.iter().collect::<String>()
+            );
+
+            // Annotate jumps taken
+            for ((_, to), taken) in self.edges.range((addr, i64::MIN)..=(addr, i64::MAX)) {
+                write!(line, "  ; -> {to} x{taken}").unwrap();
+            }
+
+            writeln!(out, "{}", line.trim_end()).unwrap();
+        }
+
+        writeln!(out, "Total {}", self.total).unwrap();
+
+        for l in &loops {
+            writeln!(
+                out,
+                "Loop {}-{}: {} iterations, {} instructions ({:.2}%)",
+                l.start,
+                l.end,
+                l.iterations,
+                l.executed,
+                self.percent(l.executed)
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    fn percent(&self, count: u64) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (count as f64 * 100.0) / self.total as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        instruction::parse_program,
+        vm::{Halt, NoIo, Vm},
+    };
+
+    use super::*;
+
+    const PROGRAM: &str = "set a 3
+set b 2
+sub b 1
+jnz b -1
+sub a 1
+jnz a -4
+";
+
+    #[test]
+    fn test_profile() {
+        let program = parse_program(PROGRAM).unwrap();
+        let mut vm = Vm::new(&program);
+
+        let (profile, stop) = Profile::run(&mut vm, &mut NoIo, None);
+
+        assert_eq!(stop, Some(Stop::Halted(Halt::Finished)));
+        assert_eq!(profile.counts(), &[1, 3, 6, 6, 3, 3]);
+        assert_eq!(profile.total(), 22);
+        assert_eq!(profile.edges()[&(3, 2)], 3);
+        assert_eq!(profile.edges()[&(5, 1)], 2);
+
+        assert_eq!(
+            profile.loops(),
+            vec![
+                Loop {
+                    start: 1,
+                    end: 5,
+                    iterations: 2,
+                    executed: 21,
+                    depth: 0
+                },
+                Loop {
+                    start: 2,
+                    end: 3,
+                    iterations: 3,
+                    executed: 12,
+                    depth: 1
+                }
+            ]
+        );
+
+        assert_eq!(
+            profile.listing(&program),
+            "addr        count       %  instruction
+   0            1   4.55%    set a 3
+   1            3  13.64%  / set b 2
+   2            6  27.27%  |/sub b 1
+   3            6  27.27%  |\\jnz b -1  ; -> 2 x3
+   4            3  13.64%  | sub a 1
+   5            3  13.64%  \\ jnz a -4  ; -> 1 x2
+Total 22
+Loop 1-5: 2 iterations, 21 instructions (95.45%)
+Loop 2-3: 3 iterations, 12 instructions (54.55%)
+"
+        );
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let program = parse_program("jnz 1 0").unwrap();
+        let mut vm = Vm::new(&program);
+
+        let (profile, stop) = Profile::run(&mut vm, &mut NoIo, Some(100));
+
+        assert_eq!(stop, None);
+        assert_eq!(profile.total(), 100);
+        assert_eq!(profile.loops()[0].iterations, 100);
+    }
+}
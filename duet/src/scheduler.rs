@@ -0,0 +1,342 @@
+use std::{collections::VecDeque, fmt, mem};
+
+use crate::{
+    compile::{Bytecode, BytecodeVm},
+    instruction::Reg,
+    vm::{Halt, Io, Rcv},
+};
+
+/// Where values sent by a program go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Routing {
+    /// Program n sends to program n + 1, with the last sending to the first
+    Ring,
+    /// Each program sends to all of the others
+    Broadcast,
+    /// Programs are paired up (0 and 1, 2 and 3...) and send to each other
+    Pairwise,
+}
+
+impl Routing {
+    /// Returns the programs a program sends to
+    fn targets(&self, sender: usize, count: usize) -> Vec<usize> {
+        match self {
+            Routing::Ring => vec![(sender + 1) % count],
+            Routing::Broadcast => (0..count).filter(|&p| p != sender).collect(),
+            Routing::Pairwise => vec![sender ^ 1],
+        }
+    }
+}
+
+/// Runs a number of copies of a program which send values to each other.
+/// Each program has register p set to its id
+pub struct Scheduler<'a> {
+    programs: Vec<Program<'a>>,
+    routing: Routing,
+}
+
+struct Program<'a> {
+    vm: BytecodeVm<'a>,
+    mailbox: Mailbox,
+}
+
+/// snd queues a value for delivery and rcv waits for a value from the inbox
+#[derive(Default)]
+struct Mailbox {
+    inbox: VecDeque<i64>,
+    outbox: Vec<i64>,
+    sent: u64,
+    received: u64,
+}
+
+impl Io for Mailbox {
+    fn snd(&mut self, value: i64) {
+        self.outbox.push(value);
+        self.sent += 1;
+    }
+
+    fn rcv(&mut self, _current: i64) -> Rcv {
+        match self.inbox.pop_front() {
+            Some(value) => {
+                self.received += 1;
+                Rcv::Store(value)
+            }
+            None => Rcv::Block,
+        }
+    }
+}
+
+impl<'a> Scheduler<'a> {
+    /// Creates a scheduler running a number of copies of a program
+    pub fn new(bytecode: &'a Bytecode, count: usize, routing: Routing) -> Result<Self, String> {
+        if count == 0 {
+            Err("At least one program is needed")?
+        }
+
+        if routing == Routing::Pairwise && !count.is_multiple_of(2) {
+            Err(format!(
+                "Pairwise routing needs an even number of programs, not {count}"
+            ))?
+        }
+
+        let p = Reg::new('p').unwrap();
+
+        let programs = (0..count)
+            .map(|id| {
+                let mut vm = BytecodeVm::new(bytecode);
+
+                vm.set_reg(p, id as i64);
+
+                Program {
+                    vm,
+                    mailbox: Mailbox::default(),
+                }
+            })
+            .collect();
+
+        Ok(Self { programs, routing })
+    }
+
+    /// Returns the virtual machine for a program
+    pub fn vm(&self, id: usize) -> &BytecodeVm<'a> {
+        &self.programs[id].vm
+    }
+
+    /// Returns the virtual machine for a program for setting up registers
+    pub fn vm_mut(&mut self, id: usize) -> &mut BytecodeVm<'a> {
+        &mut self.programs[id].vm
+    }
+
+    /// Runs the programs in turn until they have all halted or none of them can continue
+    pub fn run(&mut self) -> Report {
+        let count = self.programs.len();
+
+        loop {
+            let mut progressed = false;
+
+            for id in 0..count {
+                let program = &mut self.programs[id];
+                let steps = program.vm.steps();
+
+                program.vm.run(&mut program.mailbox);
+
+                if program.vm.steps() != steps {
+                    progressed = true;
+                }
+
+                // Deliver sent values
+                let outbox = mem::take(&mut program.mailbox.outbox);
+
+                for target in self.routing.targets(id, count) {
+                    self.programs[target]
+                        .mailbox
+                        .inbox
+                        .extend(outbox.iter().copied());
+                }
+            }
+
+            if !progressed {
+                break;
+            }
+        }
+
+        self.report()
+    }
+
+    /// Returns the state of the programs
+    pub fn report(&self) -> Report {
+        let programs = self
+            .programs
+            .iter()
+            .enumerate()
+            .map(|(id, program)| ProgramReport {
+                id,
+                sent: program.mailbox.sent,
+                received: program.mailbox.received,
+                pending: program.mailbox.inbox.len(),
+                steps: program.vm.steps(),
+                state: match program.vm.halted() {
+                    Some(halt) => State::Halted(halt),
+                    None => State::Blocked(program.vm.ip()),
+                },
+                location: match program.vm.next_instruction() {
+                    Some(instruction) => format!("{:>4}: {instruction}", program.vm.ip()),
+                    None => format!("{:>4}", program.vm.ip()),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let outcome = if programs.iter().all(|p| matches!(p.state, State::Halted(_))) {
+            Outcome::Finished
+        } else {
+            Outcome::Deadlock
+        };
+
+        Report { outcome, programs }
+    }
+}
+
+/// How a scheduler run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// All of the programs halted
+    Finished,
+    /// Some programs are blocked on empty inboxes and the rest have halted
+    Deadlock,
+}
+
+/// State a program was left in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// Blocked on a rcv at the given address
+    Blocked(i64),
+    Halted(Halt),
+}
+
+/// Statistics for a program at the end of a scheduler run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramReport {
+    pub id: usize,
+    /// Number of values sent
+    pub sent: u64,
+    /// Number of values received
+    pub received: u64,
+    /// Number of values left in the inbox
+    pub pending: usize,
+    /// Number of instructions executed
+    pub steps: u64,
+    pub state: State,
+    /// Address and instruction the program stopped at
+    pub location: String,
+}
+
+/// Result of a scheduler run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub outcome: Outcome,
+    pub programs: Vec<ProgramReport>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?}", self.outcome)?;
+
+        for p in &self.programs {
+            let state = match p.state {
+                State::Blocked(_) => "blocked".to_string(),
+                State::Halted(halt) => format!("halted ({halt})"),
+            };
+
+            writeln!(
+                f,
+                "Program {}: sent {}, received {}, {} pending, {} steps, {state} at {}",
+                p.id, p.sent, p.received, p.pending, p.steps, p.location
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::parse_program;
+
+    use super::*;
+
+    fn run(listing: &str, count: usize, routing: Routing) -> Report {
+        let program = parse_program(listing).unwrap();
+        let bytecode = Bytecode::compile(&program);
+
+        Scheduler::new(&bytecode, count, routing).unwrap().run()
+    }
+
+    #[test]
+    fn test_ring() {
+        // Program 0 starts a token round the ring and each program adds one and
+        // passes it on. The program which takes it to 10 stops, leaving the others
+        // waiting
+        let report = run(
+            "jgz p 2
+snd 0
+rcv a
+add a 1
+set b a
+sub b 9
+jgz b 3
+snd a
+jgz 1 -6
+",
+            3,
+            Routing::Ring,
+        );
+
+        assert_eq!(report.outcome, Outcome::Deadlock);
+        assert_eq!(
+            report
+                .programs
+                .iter()
+                .map(|p| (p.sent, p.received))
+                .collect::<Vec<_>>(),
+            vec![(4, 3), (3, 4), (3, 3)]
+        );
+
+        assert_eq!(report.programs[0].state, State::Blocked(2));
+        assert_eq!(report.programs[1].state, State::Halted(Halt::Finished));
+        assert_eq!(report.programs[2].state, State::Blocked(2));
+
+        assert_eq!(
+            report.to_string(),
+            "Deadlock
+Program 0: sent 4, received 3, 0 pending, 23 steps, blocked at    2: rcv a
+Program 1: sent 3, received 4, 0 pending, 27 steps, halted (finished) at    9
+Program 2: sent 3, received 3, 0 pending, 22 steps, blocked at    2: rcv a
+"
+        );
+    }
+
+    #[test]
+    fn test_broadcast() {
+        // Each program sends its id then sums what it receives
+        let report = run(
+            "snd p
+set c 3
+rcv a
+add s a
+sub c 1
+jgz c -3
+",
+            4,
+            Routing::Broadcast,
+        );
+
+        assert_eq!(report.outcome, Outcome::Finished);
+        assert!(report
+            .programs
+            .iter()
+            .all(|p| p.sent == 1 && p.received == 3));
+    }
+
+    #[test]
+    fn test_pairwise() {
+        let program = parse_program("snd p\nrcv a\nrcv a").unwrap();
+        let bytecode = Bytecode::compile(&program);
+
+        assert!(Scheduler::new(&bytecode, 3, Routing::Pairwise).is_err());
+        assert!(Scheduler::new(&bytecode, 0, Routing::Ring).is_err());
+
+        let mut scheduler = Scheduler::new(&bytecode, 4, Routing::Pairwise).unwrap();
+        let report = scheduler.run();
+
+        assert_eq!(report.outcome, Outcome::Deadlock);
+
+        // Each program gets its partner's id
+        for id in 0..4 {
+            assert_eq!(
+                scheduler.vm(id).reg(Reg::new('a').unwrap()),
+                (id ^ 1) as i64
+            );
+            assert_eq!(report.programs[id].state, State::Blocked(2));
+        }
+    }
+}
@@ -0,0 +1,487 @@
+use std::fmt;
+
+use crate::instruction::{Instruction, Reg, RegImm, REGISTERS};
+
+/// Semantics for the snd and rcv instructions
+pub trait Io {
+    /// Handles snd with the operand value
+    fn snd(&mut self, value: i64);
+
+    /// Handles rcv with the current value of the register
+    fn rcv(&mut self, current: i64) -> Rcv;
+}
+
+/// Outcome of a rcv instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rcv {
+    /// Store the value in the register and continue
+    Store(i64),
+    /// Leave the register alone and continue
+    Skip,
+    /// Wait on the rcv instruction until it can be retried
+    Block,
+    /// Stop the program
+    Halt,
+}
+
+/// Io for programs which don't communicate. snd is ignored and rcv halts
+pub struct NoIo;
+
+impl Io for NoIo {
+    fn snd(&mut self, _value: i64) {}
+
+    fn rcv(&mut self, _current: i64) -> Rcv {
+        Rcv::Halt
+    }
+}
+
+/// Result of executing a single instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The instruction at the given address was executed
+    Executed(usize),
+    /// The program is blocked on a rcv
+    Blocked,
+    /// The program has stopped
+    Halted(Halt),
+}
+
+/// Result of running a program until it can't continue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Blocked,
+    Halted(Halt),
+}
+
+/// Reason a program stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    /// The instruction pointer reached the end of the program
+    Finished,
+    /// A jump left the program for the given address
+    JumpedOut(i64),
+    /// A rcv stopped the program
+    Received,
+    /// mod by zero at the given address
+    DivideByZero(usize),
+    /// Arithmetic overflow at the given address
+    Overflow(usize),
+}
+
+impl Halt {
+    /// Returns the reason the program stopped at an address outside of it
+    pub fn left(ip: i64, len: usize) -> Self {
+        if ip == len as i64 {
+            Halt::Finished
+        } else {
+            Halt::JumpedOut(ip)
+        }
+    }
+
+    /// Returns true if the program stopped on an error rather than leaving the
+    /// program or on a rcv
+    pub fn is_fault(&self) -> bool {
+        matches!(self, Halt::DivideByZero(_) | Halt::Overflow(_))
+    }
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Halt::Finished => write!(f, "finished"),
+            Halt::JumpedOut(target) => write!(f, "jumped out of range to {target}"),
+            Halt::Received => write!(f, "stopped by rcv"),
+            Halt::DivideByZero(addr) => write!(f, "division by zero at instruction {addr}"),
+            Halt::Overflow(addr) => write!(f, "overflow at instruction {addr}"),
+        }
+    }
+}
+
+/// Operation a virtual machine can execute
+pub trait Op: Copy {
+    /// Executes the operation at the given address, returning the next instruction pointer
+    /// or the reason the program didn't continue
+    fn execute<I: Io + ?Sized>(
+        self,
+        registers: &mut [i64; REGISTERS],
+        ip: i64,
+        io: &mut I,
+    ) -> Result<i64, Stop>;
+}
+
+impl Op for Instruction {
+    fn execute<I: Io + ?Sized>(
+        self,
+        registers: &mut [i64; REGISTERS],
+        ip: i64,
+        io: &mut I,
+    ) -> Result<i64, Stop> {
+        let value = |registers: &[i64; REGISTERS], regimm: RegImm| match regimm {
+            RegImm::Reg(reg) => registers[reg.index()],
+            RegImm::Imm(imm) => imm,
+        };
+
+        match self {
+            Instruction::Snd(x) => io.snd(value(registers, x)),
+            Instruction::Set(x, y) => registers[x.index()] = value(registers, y),
+            Instruction::Add(x, y) => {
+                let result = registers[x.index()].checked_add(value(registers, y));
+                return store(registers, x.index(), result, ip);
+            }
+            Instruction::Sub(x, y) => {
+                let result = registers[x.index()].checked_sub(value(registers, y));
+                return store(registers, x.index(), result, ip);
+            }
+            Instruction::Mul(x, y) => {
+                let result = registers[x.index()].checked_mul(value(registers, y));
+                return store(registers, x.index(), result, ip);
+            }
+            Instruction::Mod(x, y) => {
+                let y = value(registers, y);
+                return modulo(registers, x.index(), y, ip);
+            }
+            Instruction::Rcv(x) => return rcv(registers, x.index(), ip, io),
+            Instruction::Jgz(x, y) => {
+                if value(registers, x) > 0 {
+                    return Ok(ip.saturating_add(value(registers, y)));
+                }
+            }
+            Instruction::Jnz(x, y) => {
+                if value(registers, x) != 0 {
+                    return Ok(ip.saturating_add(value(registers, y)));
+                }
+            }
+        }
+
+        Ok(ip + 1)
+    }
+}
+
+/// Stores the result of an arithmetic operation and moves on, halting if it overflowed
+#[inline(always)]
+pub(crate) fn store(
+    registers: &mut [i64; REGISTERS],
+    x: usize,
+    value: Option<i64>,
+    ip: i64,
+) -> Result<i64, Stop> {
+    match value {
+        Some(value) => {
+            registers[x] = value;
+            Ok(ip + 1)
+        }
+        None => Err(fault(Halt::Overflow(ip as usize))),
+    }
+}
+
+/// Stores x mod y and moves on, halting if y is zero or the result overflowed
+#[inline(always)]
+pub(crate) fn modulo(
+    registers: &mut [i64; REGISTERS],
+    x: usize,
+    y: i64,
+    ip: i64,
+) -> Result<i64, Stop> {
+    if y == 0 {
+        return Err(fault(Halt::DivideByZero(ip as usize)));
+    }
+
+    store(registers, x, registers[x].checked_rem(y), ip)
+}
+
+/// Handles a rcv into register x
+#[inline(always)]
+pub(crate) fn rcv<I: Io + ?Sized>(
+    registers: &mut [i64; REGISTERS],
+    x: usize,
+    ip: i64,
+    io: &mut I,
+) -> Result<i64, Stop> {
+    match io.rcv(registers[x]) {
+        Rcv::Store(value) => registers[x] = value,
+        Rcv::Skip => (),
+        Rcv::Block => return Err(Stop::Blocked),
+        Rcv::Halt => return Err(Stop::Halted(Halt::Received)),
+    }
+
+    Ok(ip + 1)
+}
+
+#[cold]
+fn fault(halt: Halt) -> Stop {
+    Stop::Halted(halt)
+}
+
+/// Virtual machine running a program of operations, each compiled from the instruction
+/// at the same address
+#[derive(Clone)]
+pub struct Machine<'a, O> {
+    program: &'a [Instruction],
+    ops: &'a [O],
+    registers: [i64; REGISTERS],
+    ip: i64,
+    steps: u64,
+    halted: Option<Halt>,
+}
+
+/// Virtual machine interpreting the instructions directly
+pub type Vm<'a> = Machine<'a, Instruction>;
+
+impl<'a> Vm<'a> {
+    /// Creates a new virtual machine with all registers zeroed
+    pub fn new(program: &'a [Instruction]) -> Self {
+        Self::with_ops(program, program)
+    }
+}
+
+impl<'a, O: Op> Machine<'a, O> {
+    /// Creates a new virtual machine running operations compiled from a program
+    pub(crate) fn with_ops(program: &'a [Instruction], ops: &'a [O]) -> Self {
+        debug_assert_eq!(program.len(), ops.len());
+
+        Self {
+            program,
+            ops,
+            registers: [0; REGISTERS],
+            ip: 0,
+            steps: 0,
+            halted: None,
+        }
+    }
+
+    /// Returns the program
+    pub fn program(&self) -> &'a [Instruction] {
+        self.program
+    }
+
+    /// Returns the instruction pointer
+    pub fn ip(&self) -> i64 {
+        self.ip
+    }
+
+    /// Returns the number of instructions executed
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Returns the halt reason if the program has stopped
+    pub fn halted(&self) -> Option<Halt> {
+        self.halted
+    }
+
+    /// Returns the value of a register
+    pub fn reg(&self, reg: Reg) -> i64 {
+        self.registers[reg.index()]
+    }
+
+    /// Sets the value of a register
+    pub fn set_reg(&mut self, reg: Reg, value: i64) {
+        self.registers[reg.index()] = value;
+    }
+
+    /// Returns all register values
+    pub fn registers(&self) -> &[i64; REGISTERS] {
+        &self.registers
+    }
+
+    /// Returns the next instruction to execute, if any
+    pub fn next_instruction(&self) -> Option<&'a Instruction> {
+        usize::try_from(self.ip)
+            .ok()
+            .and_then(|ip| self.program.get(ip))
+    }
+
+    /// Executes a single instruction
+    #[inline]
+    pub fn step<I: Io + ?Sized>(&mut self, io: &mut I) -> Step {
+        if let Some(halt) = self.halted {
+            return Step::Halted(halt);
+        }
+
+        let ip = self.ip as usize;
+
+        match self.execute(io) {
+            Ok(()) => Step::Executed(ip),
+            Err(Stop::Blocked) => Step::Blocked,
+            Err(Stop::Halted(halt)) => Step::Halted(halt),
+        }
+    }
+
+    /// Runs the program until it blocks or halts
+    pub fn run<I: Io + ?Sized>(&mut self, io: &mut I) -> Stop {
+        if let Some(halt) = self.halted {
+            return Stop::Halted(halt);
+        }
+
+        loop {
+            if let Err(stop) = self.execute(io) {
+                break stop;
+            }
+        }
+    }
+
+    /// Executes the next operation, returning the reason the program didn't continue
+    #[inline(always)]
+    fn execute<I: Io + ?Sized>(&mut self, io: &mut I) -> Result<(), Stop> {
+        // Negative instruction pointers wrap to large values which are out of range
+        let Some(op) = self.ops.get(self.ip as usize) else {
+            return Err(self.halt(Halt::left(self.ip, self.ops.len())));
+        };
+
+        match op.execute(&mut self.registers, self.ip, io) {
+            Ok(next_ip) => {
+                self.ip = next_ip;
+                self.steps += 1;
+
+                Ok(())
+            }
+            Err(Stop::Halted(halt)) => {
+                // A rcv which stops the program still counts as executed
+                if halt == Halt::Received {
+                    self.steps += 1;
+                }
+
+                Err(self.halt(halt))
+            }
+            Err(Stop::Blocked) => Err(Stop::Blocked),
+        }
+    }
+
+    fn halt(&mut self, halt: Halt) -> Stop {
+        self.halted = Some(halt);
+        Stop::Halted(halt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crate::{instruction::parse_program, io::Queue};
+
+    use super::*;
+
+    fn reg(name: char) -> Reg {
+        Reg::new(name).unwrap()
+    }
+
+    #[test]
+    fn test_loop() {
+        // Sum 1 to 10
+        let program = parse_program(
+            "set a 10
+add b a
+sub a 1
+jnz a -2
+mul b 2
+mod b 7
+",
+        )
+        .unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::Finished));
+        assert_eq!(vm.reg(reg('b')), 110 % 7);
+        assert_eq!(vm.ip(), 6);
+        assert_eq!(vm.steps(), 33);
+
+        // Stays halted
+        assert_eq!(vm.step(&mut NoIo), Step::Halted(Halt::Finished));
+    }
+
+    #[test]
+    fn test_jump_out() {
+        let program = parse_program("jgz 1 -1").unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.step(&mut NoIo), Step::Executed(0));
+        assert_eq!(vm.step(&mut NoIo), Step::Halted(Halt::JumpedOut(-1)));
+        assert_eq!(vm.ip(), -1);
+
+        // Jumping to just after the end finishes normally
+        let program = parse_program(
+            "jgz 1 2
+snd 1",
+        )
+        .unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::Finished));
+
+        // Huge offsets don't wrap round
+        let program = parse_program(&format!("set a {}\njgz 1 a", i64::MAX)).unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::JumpedOut(i64::MAX)));
+    }
+
+    #[test]
+    fn test_faults() {
+        let program = parse_program("set a 5\nmod a b").unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::DivideByZero(1)));
+        assert_eq!(vm.ip(), 1);
+        assert_eq!(vm.steps(), 1);
+        assert_eq!(vm.reg(reg('a')), 5);
+        assert!(vm.halted().unwrap().is_fault());
+
+        let program = parse_program("set a 2\nmul a a\njgz 1 -1").unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::Overflow(1)));
+        assert_eq!(vm.reg(reg('a')), 1 << 32);
+        assert_eq!(
+            vm.halted().unwrap().to_string(),
+            "overflow at instruction 1"
+        );
+
+        // i64::MIN % -1 overflows
+        let program = parse_program(&format!("set a {}\nmod a -1", i64::MIN)).unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::Overflow(1)));
+    }
+
+    #[test]
+    fn test_io() {
+        let program = parse_program(
+            "rcv a
+add a 1
+snd a
+jgz 1 -3
+",
+        )
+        .unwrap();
+
+        let mut vm = Vm::new(&program);
+
+        let mut io = Queue {
+            input: VecDeque::from([1, 5]),
+            output: Vec::new(),
+        };
+
+        assert_eq!(vm.run(&mut io), Stop::Blocked);
+        assert_eq!(io.output, vec![2, 6]);
+        assert_eq!(vm.ip(), 0);
+
+        // Resumes on the rcv
+        io.input.push_back(10);
+
+        assert_eq!(vm.run(&mut io), Stop::Blocked);
+        assert_eq!(io.output, vec![2, 6, 11]);
+
+        // rcv can stop the program
+        let mut vm = Vm::new(&program);
+
+        assert_eq!(vm.run(&mut NoIo), Stop::Halted(Halt::Received));
+        assert_eq!(vm.steps(), 1);
+    }
+}
@@ -0,0 +1,434 @@
+use std::{
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+    process::{exit, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+use aoc::input::{profiles, PART_VAR, PROFILE_VAR};
+
+mod pool;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Parse command line
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!("Usage: runner [--profiles] [--jobs n] [day...]");
+            exit(1);
+        }
+    };
+
+    // Day binaries are built alongside the runner
+    let bin_dir = env::current_exe()?
+        .parent()
+        .expect("No executable directory")
+        .to_path_buf();
+
+    let start = Instant::now();
+
+    let ok = if args.profiles {
+        run_profiles(&bin_dir, &args.days, args.jobs)?
+    } else {
+        run_days(&bin_dir, &args.days, args.jobs)
+    };
+
+    println!("Total time {:.2?}", start.elapsed());
+
+    if !ok {
+        exit(2);
+    }
+
+    Ok(())
+}
+
+/// Runs each day against the selected input and prints the answers
+fn run_days(bin_dir: &Path, days: &[usize], threads: usize) -> bool {
+    let mut ok = true;
+
+    // One job for each part of each day, or for the whole day if its parts share work
+    let jobs = days
+        .iter()
+        .flat_map(|day| parts(*day).iter().map(move |part| (*day, *part)))
+        .collect::<Vec<_>>();
+
+    let mut results = Vec::new();
+
+    pool::run_ordered(
+        &jobs,
+        threads,
+        |(day, part)| run_day(bin_dir, *day, *part, None),
+        |job, result| {
+            let (day, part) = jobs[job];
+
+            results.push(result);
+
+            // Print the day when its last part is done
+            if Some(&part) != parts(day).last() {
+                return;
+            }
+
+            match merge(results.drain(..)) {
+                Ok(result) => {
+                    println!("Day {day:02} ({:.2?})", result.elapsed);
+
+                    for (label, answer) in result.answers {
+                        println!("  {label}: {answer}");
+                    }
+                }
+                Err(e) => {
+                    println!("Day {day:02} failed: {e}");
+                    ok = false;
+                }
+            }
+        },
+    );
+
+    ok
+}
+
+/// Runs each day against every input profile and tabulates the answers
+fn run_profiles(bin_dir: &Path, days: &[usize], threads: usize) -> Result<bool, Box<dyn Error>> {
+    let mut ok = true;
+
+    // Default inputs followed by each named profile
+    let profiles = [None]
+        .into_iter()
+        .chain(profiles()?.into_iter().map(Some))
+        .collect::<Vec<_>>();
+
+    // One job for each part (or whole day) of each day and profile
+    let jobs = days
+        .iter()
+        .flat_map(|day| {
+            profiles.iter().flat_map(move |profile| {
+                parts(*day)
+                    .iter()
+                    .map(move |part| (*day, profile.as_deref(), *part))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut results = Vec::new();
+    let mut table = Vec::new();
+
+    pool::run_ordered(
+        &jobs,
+        threads,
+        // An empty profile selects the default inputs
+        |(day, profile, part)| run_day(bin_dir, *day, *part, Some(profile.unwrap_or(""))),
+        |job, result| {
+            let (day, profile, part) = jobs[job];
+
+            results.push(result);
+
+            if Some(&part) != parts(day).last() {
+                return;
+            }
+
+            let result = merge(results.drain(..));
+
+            ok &= result.is_ok();
+            table.push((profile.unwrap_or("default").to_string(), result));
+
+            // Print the table when the last profile for this day is done
+            if table.len() == profiles.len() {
+                println!("Day {day:02}");
+                print_profile_table(table.drain(..));
+                println!();
+            }
+        },
+    );
+
+    Ok(ok)
+}
+
+/// Prints the answers for a day from each input profile
+fn print_profile_table(table: impl Iterator<Item = (String, Result<DayResult, String>)>) {
+    let table = table.collect::<Vec<_>>();
+
+    // Collect answer labels
+    let mut labels: Vec<String> = Vec::new();
+
+    for (label, _) in table
+        .iter()
+        .filter_map(|(_, result)| result.as_ref().ok())
+        .flat_map(|result| result.answers.iter())
+    {
+        if !labels.contains(label) {
+            labels.push(label.clone());
+        }
+    }
+
+    // Build table rows
+    let mut rows = vec![[
+        vec!["Profile".to_string()],
+        labels.clone(),
+        vec!["Time".to_string()],
+    ]
+    .concat()];
+
+    for (name, result) in table {
+        let row = match result {
+            Ok(result) => {
+                let answers = labels.iter().map(|l| {
+                    result
+                        .answers
+                        .iter()
+                        .find_map(|(label, answer)| (label == l).then(|| answer.clone()))
+                        .unwrap_or_default()
+                });
+
+                [name]
+                    .into_iter()
+                    .chain(answers)
+                    .chain([format!("{:.2?}", result.elapsed)])
+                    .collect()
+            }
+            Err(e) => vec![name, format!("failed: {e}")],
+        };
+
+        rows.push(row);
+    }
+
+    print_table(&rows);
+}
+
+/// Prints rows of cells with aligned columns
+fn print_table(rows: &[Vec<String>]) {
+    let cols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+
+    let widths = (0..cols)
+        .map(|c| {
+            rows.iter()
+                .filter(|r| r.len() > c + 1)
+                .map(|r| r[c].len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    for row in rows {
+        let line = row
+            .iter()
+            .enumerate()
+            .map(|(c, cell)| format!("{cell:<width$}", width = widths[c]))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        println!("  {}", line.trim_end());
+    }
+}
+
+/// Output from a day binary
+struct DayResult {
+    answers: Vec<(String, String)>,
+    elapsed: Duration,
+}
+
+/// Days whose answers both come from one computation
+const SHARED_DAYS: [usize; 5] = [6, 9, 14, 19, 24];
+
+/// Returns the jobs for a day as the part each one runs. Days with independent parts
+/// run each part on its own, and other days (including the last, which only has one
+/// part) run as a whole so shared work isn't repeated
+fn parts(day: usize) -> &'static [Option<usize>] {
+    if day == 25 || SHARED_DAYS.contains(&day) {
+        &[None]
+    } else {
+        &[Some(1), Some(2)]
+    }
+}
+
+/// Combines the results of running each part of a day. The day takes as long as
+/// its slowest part and fails if any part fails
+fn merge(results: impl Iterator<Item = Result<DayResult, String>>) -> Result<DayResult, String> {
+    let mut merged = DayResult {
+        answers: Vec::new(),
+        elapsed: Duration::ZERO,
+    };
+
+    for result in results {
+        let result = result?;
+
+        merged.answers.extend(result.answers);
+        merged.elapsed = merged.elapsed.max(result.elapsed);
+    }
+
+    Ok(merged)
+}
+
+/// Runs one part (or all parts) of a day binary, optionally overriding the input
+/// profile, and collects the answers
+fn run_day(
+    bin_dir: &Path,
+    day: usize,
+    part: Option<usize>,
+    profile: Option<&str>,
+) -> Result<DayResult, String> {
+    let bin = day_binary(bin_dir, day);
+
+    if !bin.exists() {
+        return Err(format!(
+            "{} not found (build with cargo build --workspace)",
+            bin.display()
+        ));
+    }
+
+    let mut command = Command::new(&bin);
+
+    if let Some(part) = part {
+        command.env(PART_VAR, part.to_string());
+    }
+
+    if let Some(profile) = profile {
+        command.env(PROFILE_VAR, profile);
+    }
+
+    let start = Instant::now();
+    let output = command.output().map_err(|e| e.to_string())?;
+    let elapsed = start.elapsed();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        return Err(stderr
+            .lines()
+            .rfind(|l| !l.trim().is_empty())
+            .unwrap_or("no output")
+            .to_string());
+    }
+
+    Ok(DayResult {
+        answers: parse_answers(&String::from_utf8_lossy(&output.stdout)),
+        elapsed,
+    })
+}
+
+/// Returns the path of a day binary
+fn day_binary(bin_dir: &Path, day: usize) -> PathBuf {
+    bin_dir.join(format!("day{day:02}{}", env::consts::EXE_SUFFIX))
+}
+
+/// Extracts "Part N: answer" lines from day output
+fn parse_answers(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter(|l| l.starts_with("Part "))
+        .filter_map(|l| l.split_once(": "))
+        .map(|(label, answer)| (label.to_string(), answer.to_string()))
+        .collect()
+}
+
+/// Command line arguments
+struct Args {
+    profiles: bool,
+    jobs: usize,
+    days: Vec<usize>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut profiles = false;
+        let mut jobs = thread::available_parallelism().map_or(1, |n| n.get());
+        let mut days = Vec::new();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--profiles" => profiles = true,
+                "-j" | "--jobs" => {
+                    jobs = args
+                        .next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|n| *n > 0)
+                        .ok_or("--jobs needs a number of jobs")?
+                }
+                _ => match arg.parse::<usize>() {
+                    Ok(day) if (1..=25).contains(&day) => days.push(day),
+                    _ => Err(format!("Invalid argument {arg}"))?,
+                },
+            }
+        }
+
+        if days.is_empty() {
+            days = (1..=25).collect();
+        }
+
+        Ok(Self {
+            profiles,
+            jobs,
+            days,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let args = Args::parse(
+            ["--profiles", "7", "--jobs", "3", "12"]
+                .into_iter()
+                .map(String::from),
+        )
+        .unwrap();
+        assert!(args.profiles);
+        assert_eq!(args.jobs, 3);
+        assert_eq!(args.days, vec![7, 12]);
+
+        let args = Args::parse([].into_iter()).unwrap();
+        assert!(!args.profiles);
+        assert_eq!(args.days.len(), 25);
+
+        assert!(Args::parse(["26"].into_iter().map(String::from)).is_err());
+        assert!(Args::parse(["-j", "0"].into_iter().map(String::from)).is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let part = |label: &str, ms: u64| {
+            Ok(DayResult {
+                answers: vec![(label.to_string(), ms.to_string())],
+                elapsed: Duration::from_millis(ms),
+            })
+        };
+
+        let merged = merge([part("Part 1", 5), part("Part 2", 8)].into_iter()).unwrap();
+
+        assert_eq!(
+            merged.answers,
+            vec![
+                ("Part 1".to_string(), "5".to_string()),
+                ("Part 2".to_string(), "8".to_string())
+            ]
+        );
+        assert_eq!(merged.elapsed, Duration::from_millis(8));
+
+        assert_eq!(
+            merge([part("Part 1", 5), Err("failed".to_string())].into_iter()).err(),
+            Some("failed".to_string())
+        );
+
+        assert_eq!(parts(7), [Some(1), Some(2)]);
+        assert_eq!(parts(24), [None]);
+        assert_eq!(parts(25), [None]);
+    }
+
+    #[test]
+    fn test_answers() {
+        let answers = parse_answers("Part 1: tknk\nsome noise\nPart 2: 60\n");
+
+        assert_eq!(
+            answers,
+            vec![
+                ("Part 1".to_string(), "tknk".to_string()),
+                ("Part 2".to_string(), "60".to_string())
+            ]
+        );
+    }
+}
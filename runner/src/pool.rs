@@ -0,0 +1,78 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+/// Runs a work function over a list of jobs on a number of worker threads.
+/// Results are passed to the output function in job order as soon as they are available
+pub fn run_ordered<J, R, W, O>(jobs: &[J], threads: usize, work: W, mut output: O)
+where
+    J: Sync,
+    R: Send,
+    W: Fn(&J) -> R + Sync,
+    O: FnMut(usize, R),
+{
+    let next_job = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        // Start the workers
+        for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+            let tx = tx.clone();
+            let next_job = &next_job;
+            let work = &work;
+
+            scope.spawn(move || loop {
+                let job = next_job.fetch_add(1, Ordering::SeqCst);
+
+                if job >= jobs.len() || tx.send((job, work(&jobs[job]))).is_err() {
+                    break;
+                }
+            });
+        }
+
+        drop(tx);
+
+        // Collect results, releasing them in order
+        let mut pending = (0..jobs.len()).map(|_| None).collect::<Vec<_>>();
+        let mut next_out = 0;
+
+        for (job, result) in rx {
+            pending[job] = Some(result);
+
+            while let Some(result) = pending.get_mut(next_out).and_then(Option::take) {
+                output(next_out, result);
+                next_out += 1;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        // Earlier jobs take longest
+        let jobs = (0..8u64).rev().collect::<Vec<_>>();
+        let mut out = Vec::new();
+
+        run_ordered(
+            &jobs,
+            4,
+            |ms| {
+                thread::sleep(Duration::from_millis(*ms * 5));
+                *ms
+            },
+            |job, result| out.push((job, result)),
+        );
+
+        assert_eq!(out, jobs.into_iter().enumerate().collect::<Vec<_>>());
+    }
+}
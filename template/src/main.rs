@@ -1,31 +1,36 @@
 use std::error::Error;
 
-use aoc::input::parse_input_vec;
+use aoc::input::{parse_input_vec, run_part};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Get input
     let input = parse_input_vec($day, input_transform)?;
 
     // Run parts
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    if run_part(1) {
+        println!("Part 1: {}", part1(&input));
+    }
+
+    if run_part(2) {
+        println!("Part 2: {}", part2(&input));
+    }
 
     Ok(())
 }
 
-fn part1(input: &[InputEnt]) -> u64 {
+pub fn part1(input: &[InputEnt]) -> u64 {
     0 // TODO
 }
 
-fn part2(input: &[InputEnt]) -> u64 {
+pub fn part2(input: &[InputEnt]) -> u64 {
     0 // TODO
 }
 
 // Input parsing
 
-type InputEnt = String; // TODO
+pub type InputEnt = String; // TODO
 
-fn input_transform(line: String) -> InputEnt {
+pub fn input_transform(line: String) -> InputEnt {
     // TODO
     line
 }